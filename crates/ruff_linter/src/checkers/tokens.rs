@@ -30,6 +30,7 @@ pub(crate) fn check_tokens(
     settings: &LinterSettings,
     source_type: PySourceType,
     cell_offsets: Option<&CellOffsets>,
+    has_bom: bool,
 ) -> Vec<Diagnostic> {
     let mut diagnostics: Vec<Diagnostic> = vec![];
 
@@ -85,6 +86,25 @@ pub(crate) fn check_tokens(
         pyupgrade::rules::unnecessary_coding_comment(&mut diagnostics, locator, indexer);
     }
 
+    // `BadFileEncoding` flags cookies that declare a *disallowed* encoding, while
+    // `UTF8EncodingDeclaration` (above) flags cookies that are *redundant* because they
+    // already say UTF-8. `UTF8EncodingDeclaration` isn't given the settings, so it doesn't
+    // consult `allowed-encodings`. With the default allow-list, which lists the UTF-8 aliases,
+    // a cookie can only match one of the two. An allow-list that leaves out a UTF-8 spelling
+    // lets both rules flag the same cookie: `BadFileEncoding` offers to rewrite it to `utf-8`
+    // while `UTF8EncodingDeclaration` offers to remove it.
+    if settings.rules.enabled(Rule::BadFileEncoding) {
+        if let Some(diagnostic) = pylint::rules::bad_file_encoding(locator, settings) {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    if settings.rules.enabled(Rule::BomConflictingEncoding) {
+        if let Some(diagnostic) = pylint::rules::bom_conflicting_encoding(locator, has_bom) {
+            diagnostics.push(diagnostic);
+        }
+    }
+
     if settings.rules.enabled(Rule::InvalidEscapeSequence) {
         for (tok, range) in tokens.iter().flatten() {
             pycodestyle::rules::invalid_escape_sequence(
@@ -0,0 +1,514 @@
+//! A small structural search-and-replace engine for AST pattern rules.
+//!
+//! `bad_super_call` and its siblings each hand-wrote their own little bit of tree matching:
+//! compare this expression to that one, walk these particular fields, rebuild a replacement
+//! string from the pieces that didn't match anything. This module generalizes that shape into a
+//! declarative `pattern ==>> template` rule that can be parsed once and matched against any
+//! [`Expr`], so that a rule like `Base.method(self, $args...) ==>> super().method($args...)`
+//! doesn't need a bespoke Rust function to express. Placeholders stand only where an expression
+//! goes; attribute names are always literal identifiers, so `value.$name` doesn't parse.
+//!
+//! A pattern is a tiny expression language of its own — not Python, since placeholders like
+//! `$cls` aren't valid Python syntax — covering the subset of shapes these rewrites need: bare
+//! names, dotted attribute access, and calls. [`Pattern::parse`] reads that language; [`is_match`]
+//! unifies a parsed pattern against a real [`Expr`], binding each placeholder it encounters;
+//! [`render`] renders a template back out to source text using those bindings, by splicing in the
+//! original source of whatever each placeholder matched.
+//!
+//! Constraints that depend on context outside the matched subtree itself — "`$cls` must equal the
+//! enclosing class", say — aren't expressible in the pattern language, since the pattern only ever
+//! sees the one expression it's matching against. Callers that need that supply a `validate`
+//! callback to [`is_match`] instead; this keeps the matcher itself ignorant of what a "class" or an
+//! "enclosing scope" even are.
+//!
+//! [`rule_fix`] is the other half of the bridge: given a rule, a matched expression, and a
+//! [`Locator`], it runs the match-then-render pipeline end to end and hands back an [`Edit`] ready
+//! to attach to a [`Diagnostic`] via [`Fix::safe_edit`](ruff_diagnostics::Fix::safe_edit), so a
+//! rule like `bad_super_call`'s can be expressed as data instead of a hand-rolled splicer.
+//!
+//! This module is the matching/rendering/autofix core only. Loading rules from
+//! `pyproject.toml`/`ruff.toml` and registering them alongside the built-in rules would live in the
+//! settings layer, which isn't part of this snapshot.
+
+use std::collections::HashMap;
+
+use ruff_diagnostics::Edit;
+use ruff_python_ast::comparable::ComparableExpr;
+use ruff_python_ast::Expr;
+use ruff_source_file::Locator;
+use ruff_text_size::Ranged;
+
+/// A parsed `pattern ==>> template` rule, e.g. `super($cls, $self) ==>> super()`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PatternRule {
+    pub name: String,
+    pub pattern: Pattern,
+    pub template: Pattern,
+}
+
+impl PatternRule {
+    /// Parses a rule of the form `<pattern> ==>> <template>`.
+    pub fn parse(name: impl Into<String>, spec: &str) -> Result<Self, PatternParseError> {
+        let (pattern_src, template_src) = spec
+            .split_once("==>>")
+            .ok_or(PatternParseError::MissingArrow)?;
+        Ok(Self {
+            name: name.into(),
+            pattern: Pattern::parse(pattern_src.trim())?,
+            template: Pattern::parse(template_src.trim())?,
+        })
+    }
+}
+
+/// A structural pattern, matched against (or rendered as) an [`Expr`].
+///
+/// Only the shapes `bad_super_call`-style rules actually need are supported: names, dotted
+/// attribute access, and calls with positional arguments. Keyword arguments, subscripts, binary
+/// operators, and every other `Expr` variant simply never match; there's no representation for
+/// them here yet. An attribute name is always a literal identifier, never a placeholder.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// A `$name` placeholder, binding whatever it matches.
+    Placeholder(PlaceholderSpec),
+    /// A literal, non-placeholder identifier, e.g. the `super` in `super($cls, $self)`.
+    Name(String),
+    /// `value.attr`.
+    Attribute { value: Box<Pattern>, attr: String },
+    /// `func(args...)`.
+    Call { func: Box<Pattern>, args: Vec<Pattern> },
+}
+
+/// A single named placeholder slot, e.g. `$cls` or the variadic `$args`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaceholderSpec {
+    pub name: String,
+    /// Whether this placeholder soaks up zero or more trailing call arguments (`$args`) rather
+    /// than binding exactly one expression. Only meaningful as the last element of a `Call`'s
+    /// argument list.
+    pub variadic: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PatternParseError {
+    MissingArrow,
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    EmptyPlaceholder,
+}
+
+impl Pattern {
+    pub fn parse(source: &str) -> Result<Self, PatternParseError> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        let pattern = parser.parse_postfix()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.chars.len() {
+            return Err(parser.error_here());
+        }
+        Ok(pattern)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn error_here(&self) -> PatternParseError {
+        self.peek()
+            .map_or(PatternParseError::UnexpectedEnd, PatternParseError::UnexpectedChar)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), PatternParseError> {
+        self.skip_whitespace();
+        if self.bump() == Some(expected) {
+            Ok(())
+        } else {
+            Err(self.error_here())
+        }
+    }
+
+    /// `atom ( '.' ident | '(' args ')' )*`
+    fn parse_postfix(&mut self) -> Result<Pattern, PatternParseError> {
+        let mut pattern = self.parse_atom()?;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('.') => {
+                    self.bump();
+                    let attr = self.parse_ident()?;
+                    pattern = Pattern::Attribute {
+                        value: Box::new(pattern),
+                        attr,
+                    };
+                }
+                Some('(') => {
+                    self.bump();
+                    let args = self.parse_args()?;
+                    self.expect(')')?;
+                    pattern = Pattern::Call {
+                        func: Box::new(pattern),
+                        args,
+                    };
+                }
+                _ => break,
+            }
+        }
+        Ok(pattern)
+    }
+
+    /// `placeholder | ident`
+    fn parse_atom(&mut self) -> Result<Pattern, PatternParseError> {
+        self.skip_whitespace();
+        if self.peek() == Some('$') {
+            self.bump();
+            let name = self.parse_ident()?;
+            if name.is_empty() {
+                return Err(PatternParseError::EmptyPlaceholder);
+            }
+            self.skip_whitespace();
+            let variadic = self.chars.get(self.pos..self.pos + 3) == Some(['.', '.', '.'].as_slice());
+            if variadic {
+                self.pos += 3;
+            }
+            return Ok(Pattern::Placeholder(PlaceholderSpec { name, variadic }));
+        }
+        Ok(Pattern::Name(self.parse_ident()?))
+    }
+
+    fn parse_ident(&mut self) -> Result<String, PatternParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error_here());
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// `(pattern (',' pattern)*)?`
+    fn parse_args(&mut self) -> Result<Vec<Pattern>, PatternParseError> {
+        let mut args = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(')') {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_postfix()?);
+            self.skip_whitespace();
+            if self.peek() == Some(',') {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// What a placeholder matched: either a single expression, or (for a variadic `$args` slot) the
+/// run of trailing call arguments it soaked up.
+#[derive(Debug, Clone, Copy)]
+pub enum Binding<'a> {
+    Single(&'a Expr),
+    Many(&'a [Expr]),
+}
+
+pub type Bindings<'a> = HashMap<String, Binding<'a>>;
+
+/// Matches `pattern` against `expr`, returning the placeholder bindings on success.
+///
+/// `validate` is consulted every time a placeholder binds or re-binds; it gets the placeholder's
+/// name and what it just matched, and returning `false` fails the match. This is the hook for
+/// constraints the pattern itself can't express, e.g. "`$cls` must equal the enclosing class" —
+/// the caller already knows what the enclosing class is; this module never needs to.
+pub fn is_match<'a>(
+    pattern: &Pattern,
+    expr: &'a Expr,
+    validate: &mut impl FnMut(&str, Binding<'a>) -> bool,
+) -> Option<Bindings<'a>> {
+    let mut bindings = Bindings::new();
+    match_expr(pattern, expr, &mut bindings, validate).then_some(bindings)
+}
+
+fn match_expr<'a>(
+    pattern: &Pattern,
+    expr: &'a Expr,
+    bindings: &mut Bindings<'a>,
+    validate: &mut impl FnMut(&str, Binding<'a>) -> bool,
+) -> bool {
+    match pattern {
+        Pattern::Placeholder(spec) => bind(spec, Binding::Single(expr), bindings, validate),
+        Pattern::Name(name) => expr.as_name_expr().is_some_and(|n| n.id.as_str() == name),
+        Pattern::Attribute { value, attr } => expr.as_attribute_expr().is_some_and(|attribute| {
+            attribute.attr.as_str() == attr
+                && match_expr(value, &attribute.value, bindings, validate)
+        }),
+        Pattern::Call { func, args } => expr.as_call_expr().is_some_and(|call| {
+            call.arguments.keywords.is_empty()
+                && match_expr(func, &call.func, bindings, validate)
+                && match_args(args, &call.arguments.args, bindings, validate)
+        }),
+    }
+}
+
+fn match_args<'a>(
+    patterns: &[Pattern],
+    args: &'a [Expr],
+    bindings: &mut Bindings<'a>,
+    validate: &mut impl FnMut(&str, Binding<'a>) -> bool,
+) -> bool {
+    if let Some(Pattern::Placeholder(spec)) = patterns.last() {
+        if spec.variadic {
+            let fixed = &patterns[..patterns.len() - 1];
+            if args.len() < fixed.len() {
+                return false;
+            }
+            if !fixed
+                .iter()
+                .zip(args)
+                .all(|(p, a)| match_expr(p, a, bindings, validate))
+            {
+                return false;
+            }
+            return bind(spec, Binding::Many(&args[fixed.len()..]), bindings, validate);
+        }
+    }
+    patterns.len() == args.len()
+        && patterns
+            .iter()
+            .zip(args)
+            .all(|(p, a)| match_expr(p, a, bindings, validate))
+}
+
+/// Binds `spec` to `binding`, consulting `validate` and requiring consistency with any earlier
+/// binding of the same placeholder name (a non-linear pattern like `f($x, $x)` only matches when
+/// both occurrences agree).
+fn bind<'a>(
+    spec: &PlaceholderSpec,
+    binding: Binding<'a>,
+    bindings: &mut Bindings<'a>,
+    validate: &mut impl FnMut(&str, Binding<'a>) -> bool,
+) -> bool {
+    if !validate(&spec.name, binding) {
+        return false;
+    }
+    match bindings.get(&spec.name) {
+        Some(existing) => bindings_equal(*existing, binding),
+        None => {
+            bindings.insert(spec.name.clone(), binding);
+            true
+        }
+    }
+}
+
+/// Compares bindings structurally (same shape, ignoring source position) rather than by raw
+/// `Expr` equality, so a non-linear pattern like `f($x, $x)` can re-match a placeholder that's
+/// already bound to a structurally-identical subtree found at a different offset.
+fn bindings_equal(a: Binding, b: Binding) -> bool {
+    match (a, b) {
+        (Binding::Single(a), Binding::Single(b)) => ComparableExpr::from(a) == ComparableExpr::from(b),
+        (Binding::Many(a), Binding::Many(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|(a, b)| ComparableExpr::from(a) == ComparableExpr::from(b))
+        }
+        _ => false,
+    }
+}
+
+/// Renders `template` back out to source text, splicing in `locator`'s source for each bound
+/// placeholder. Returns `None` if the template references a placeholder `bindings` has no entry
+/// for, which means `template` wasn't the template half of the same [`PatternRule`] whose pattern
+/// produced `bindings`.
+pub fn render(template: &Pattern, bindings: &Bindings, locator: &Locator) -> Option<String> {
+    match template {
+        Pattern::Placeholder(spec) => match bindings.get(&spec.name)? {
+            Binding::Single(expr) => Some(locator.slice(expr.range()).to_string()),
+            Binding::Many(exprs) => Some(
+                exprs
+                    .iter()
+                    .map(|expr| locator.slice(expr.range()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        },
+        Pattern::Name(name) => Some(name.clone()),
+        Pattern::Attribute { value, attr } => {
+            Some(format!("{}.{attr}", render(value, bindings, locator)?))
+        }
+        Pattern::Call { func, args } => {
+            let func = render(func, bindings, locator)?;
+            let args = args
+                .iter()
+                .map(|arg| render(arg, bindings, locator))
+                .collect::<Option<Vec<_>>>()?
+                .join(", ");
+            Some(format!("{func}({args})"))
+        }
+    }
+}
+
+/// Matches `expr` against `rule.pattern` and, on success, renders `rule.template` against the
+/// bindings and returns the [`Edit`] that replaces `expr` with it.
+///
+/// Returns `None` if `expr` doesn't match, or if `validate` rejects a binding.
+pub fn rule_fix(
+    rule: &PatternRule,
+    expr: &Expr,
+    locator: &Locator,
+    validate: &mut impl FnMut(&str, Binding) -> bool,
+) -> Option<Edit> {
+    let bindings = is_match(&rule.pattern, expr, validate)?;
+    let replacement = render(&rule.template, &bindings, locator)?;
+    Some(Edit::range_replacement(replacement, expr.range()))
+}
+
+#[cfg(test)]
+mod rule_fix_tests {
+    use ruff_python_ast::Stmt;
+    use ruff_python_parser::parse_module;
+    use ruff_source_file::Locator;
+
+    use super::{rule_fix, PatternRule};
+
+    #[test]
+    fn rule_fix_renders_the_matched_call() {
+        let source = "Base.method(self, a, b)\n";
+        let module = parse_module(source).expect("valid source").into_syntax();
+        let Stmt::Expr(expr_stmt) = &module.body[0] else {
+            panic!("expected an expression statement")
+        };
+
+        let rule = PatternRule::parse(
+            "explicit-base-class-call",
+            "Base.method(self, $args...) ==>> super().method($args...)",
+        )
+        .unwrap();
+        let locator = Locator::new(source);
+
+        let edit = rule_fix(&rule, &expr_stmt.value, &locator, &mut |_, _| true).unwrap();
+        assert_eq!(edit.content(), Some("super().method(a, b)"));
+    }
+
+    #[test]
+    fn rule_fix_is_none_on_a_non_match() {
+        let source = "Other.method(self, a)\n";
+        let module = parse_module(source).expect("valid source").into_syntax();
+        let Stmt::Expr(expr_stmt) = &module.body[0] else {
+            panic!("expected an expression statement")
+        };
+
+        let rule = PatternRule::parse(
+            "explicit-base-class-call",
+            "Base.method(self, $args...) ==>> super().method($args...)",
+        )
+        .unwrap();
+        let locator = Locator::new(source);
+
+        assert!(rule_fix(&rule, &expr_stmt.value, &locator, &mut |_, _| true).is_none());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pattern, PatternParseError, PatternRule, PlaceholderSpec};
+
+    #[test]
+    fn parses_bare_name() {
+        assert_eq!(Pattern::parse("super").unwrap(), Pattern::Name("super".to_string()));
+    }
+
+    #[test]
+    fn parses_placeholder() {
+        assert_eq!(
+            Pattern::parse("$cls").unwrap(),
+            Pattern::Placeholder(PlaceholderSpec {
+                name: "cls".to_string(),
+                variadic: false,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_variadic_placeholder() {
+        assert_eq!(
+            Pattern::parse("$args...").unwrap(),
+            Pattern::Placeholder(PlaceholderSpec {
+                name: "args".to_string(),
+                variadic: true,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_super_call_pattern() {
+        let pattern = Pattern::parse("super($cls, $self).$m($args...)").unwrap();
+        let Pattern::Call { func, args } = pattern else {
+            panic!("expected a call pattern");
+        };
+        assert_eq!(args.len(), 1);
+        let Pattern::Attribute { value, attr } = *func else {
+            panic!("expected the call's callee to be an attribute access");
+        };
+        assert_eq!(attr, "m");
+        let Pattern::Call { args: super_args, .. } = *value else {
+            panic!("expected `super(...)` as the attribute's base");
+        };
+        assert_eq!(super_args.len(), 2);
+    }
+
+    #[test]
+    fn rejects_missing_arrow() {
+        assert_eq!(
+            PatternRule::parse("bad", "super($cls, $self)").unwrap_err(),
+            PatternParseError::MissingArrow
+        );
+    }
+
+    #[test]
+    fn parses_full_rule() {
+        let rule =
+            PatternRule::parse("super-with-parameters", "super($cls, $self) ==>> super()").unwrap();
+        assert_eq!(rule.name, "super-with-parameters");
+        assert_eq!(rule.template, Pattern::Call {
+            func: Box::new(Pattern::Name("super".to_string())),
+            args: Vec::new(),
+        });
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        assert!(matches!(
+            Pattern::parse("super() )"),
+            Err(PatternParseError::UnexpectedChar(')'))
+        ));
+    }
+}
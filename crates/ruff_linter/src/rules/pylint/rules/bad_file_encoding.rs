@@ -1,61 +1,107 @@
-use once_cell::sync::Lazy;
-use regex::Regex;
-use ruff_diagnostics::{Diagnostic, Violation};
+use ruff_diagnostics::{Diagnostic, Edit, Fix, FixAvailability, Violation};
 use ruff_macros::{derive_message_formats, violation};
 use ruff_source_file::Locator;
 use ruff_text_size::{TextRange, TextSize};
 
-// see https://peps.python.org/pep-0263/
-// utf-8 aliases: utf8, U8, UTF, cp65001 case and _- can be used interchangebly
-// just added utf-8 to it
-static IS_ENCODING: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(^[ \t\f]*#.*?coding[:=][ \t]*((?i)u8|utf(_8|-8)?|cp65001)($| ).*)").unwrap()
-});
-static IS_UTF8_ENCODING: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(^[ \t\f]*#.*?coding[:=][ \t]*((?i)u8|utf(_8|-8)?|cp65001)($| ).*)").unwrap()
-});
+use crate::settings::LinterSettings;
+use crate::source_encoding::{is_utf8_alias, normalize_encoding_name, CODING_COOKIE};
 
 /// ## What it does
-/// Checks for the file encoding in python files and emmits a message if the file encoding is not
-/// utf-8
+/// Checks for the file encoding in python files and emmits a message if the declared (or
+/// assumed) encoding is not in the allowed list.
 ///
 /// ## Why is this bad?
 /// PEP8 recommends UTF-8 default encoding for Python files. See
 /// https://peps.python.org/pep-0008/#source-file-encoding
+///
+/// ## Options
+/// - `lint.pylint.allowed-encodings`
+///
+/// ## Fix safety
+/// The fix only fires when the declared codec is already a non-canonically spelled alias of
+/// UTF-8 (e.g. `UTF8` or `cp65001`), in which case rewriting the cookie to `utf-8` changes
+/// nothing about how the file's bytes are interpreted, so it's safe. When the declared codec is
+/// a genuinely different encoding (e.g. `latin-1`), no fix is offered at all: rewriting the
+/// cookie alone, without transcoding the file's bytes, would make Ruff claim the file is UTF-8
+/// when it isn't. Actually transcoding requires decoding the file through the declared codec
+/// before a [`Locator`] is ever built for it (see `source_encoding`), which this rule doesn't
+/// have access to. Scope note: this rule intentionally does not offer a transcoding fix for the
+/// genuinely-non-UTF-8 case — that would require `source_encoding::read_and_decode_source` to be
+/// wired into a real file-reading entry point, which doesn't exist in this tree yet.
 #[violation]
-pub struct BadFileEncoding;
+pub struct BadFileEncoding {
+    encoding: String,
+}
 
 impl Violation for BadFileEncoding {
+    const FIX_AVAILABILITY: FixAvailability = FixAvailability::Sometimes;
+
     #[derive_message_formats]
     fn message(&self) -> String {
-        format!("PEP8 recommends UTF-8 as encoding for Python files")
+        let BadFileEncoding { encoding } = self;
+        format!("PEP8 recommends UTF-8 as encoding for Python files, found `{encoding}`")
+    }
+
+    fn fix_title(&self) -> Option<String> {
+        Some("Replace the coding comment with `utf-8`".to_string())
     }
 }
 
-pub(crate) fn bad_file_encoding(locator: &Locator) -> Option<Diagnostic> {
-    // Only search the first 2 lines rest is not relevant
-    let contents = locator.up_to(locator.floor_char_boundary(TextSize::new(2)));
+pub(crate) fn bad_file_encoding(locator: &Locator, settings: &LinterSettings) -> Option<Diagnostic> {
+    // Per PEP 263, only the first two physical lines are ever consulted, and the search stops
+    // at the first coding cookie found.
+    let mut offset = TextSize::new(0);
+    for line in locator.contents().split('\n').take(2) {
+        let line_range = TextRange::at(offset, TextSize::try_from(line.len()).unwrap());
+        offset = line_range.end() + TextSize::new(1);
 
-    if IS_ENCODING.is_match(contents) && !IS_UTF8_ENCODING.is_match(contents) {
-        return Some(Diagnostic::new(BadFileEncoding, TextRange::default()));
-    }
-    // try out if there is an encoding in the second line
-    if contents.starts_with("#!") {
-        let try_second = contents.split_once('\n');
-        match try_second {
-            Some((_, second)) => {
-                if IS_ENCODING.is_match(second) && !IS_UTF8_ENCODING.is_match(second) {
-                    return Some(Diagnostic::new(BadFileEncoding, TextRange::default()));
-                }
-            }
-            None => {
-                return None;
-            }
+        let Some(captures) = CODING_COOKIE.captures(line) else {
+            continue;
+        };
+        let token = captures.get(1).unwrap();
+        let name = token.as_str();
+
+        if is_allowed_encoding(name, settings) {
+            return None;
+        }
+
+        let token_range = TextRange::new(
+            line_range.start() + TextSize::try_from(token.start()).unwrap(),
+            line_range.start() + TextSize::try_from(token.end()).unwrap(),
+        );
+
+        let mut diagnostic = Diagnostic::new(
+            BadFileEncoding {
+                encoding: name.to_string(),
+            },
+            token_range,
+        );
+
+        // Only a non-canonical spelling of UTF-8 (e.g. `UTF8`) can be rewritten to `utf-8`
+        // without touching the file's actual bytes. A genuinely different codec would need
+        // those bytes transcoded first, which this rule has no way to do.
+        if name != "utf-8" && is_utf8_alias(&normalize_encoding_name(name)) {
+            let edit = Edit::range_replacement("utf-8".to_string(), token_range);
+            diagnostic = diagnostic.with_fix(Fix::safe_edit(edit));
         }
+
+        return Some(diagnostic);
     }
     None
 }
 
+/// Returns whether `encoding` (as written in the coding cookie) matches one of the
+/// `allowed-encodings` configured for the project, comparing case-insensitively and treating
+/// `_`/`-` as interchangeable, per PEP 263.
+fn is_allowed_encoding(encoding: &str, settings: &LinterSettings) -> bool {
+    let normalized = normalize_encoding_name(encoding);
+    settings
+        .pylint
+        .allowed_encodings
+        .iter()
+        .any(|allowed| normalize_encoding_name(allowed) == normalized)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::registry::Rule;
@@ -76,6 +122,23 @@ import os, sys
         assert_messages!(diagnostics);
     }
 
+    #[test]
+    fn noncanonical_utf8_alias_is_fixed() {
+        // The default allow-list already accepts `UTF8`, so restrict it to the canonical spelling.
+        let mut settings = settings::LinterSettings::for_rules(vec![Rule::BadFileEncoding]);
+        settings.pylint.allowed_encodings = vec!["utf-8".to_string()];
+        let diagnostics = test_snippet(
+            r"
+#!/usr/bin/python
+# -*- coding: UTF8 -*-
+import os, sys
+"
+            .trim(),
+            &settings,
+        );
+        assert_messages!(diagnostics);
+    }
+
     #[test]
     fn latin1_file_encoding() {
         let diagnostics = test_snippet(
@@ -89,4 +152,20 @@ import os, sys
         );
         assert_messages!(diagnostics);
     }
+
+    #[test]
+    fn allowed_encodings_permits_non_utf8() {
+        let mut settings = settings::LinterSettings::for_rules(vec![Rule::BadFileEncoding]);
+        settings.pylint.allowed_encodings = vec!["latin-1".to_string()];
+        let diagnostics = test_snippet(
+            r"
+#!/usr/bin/python
+# -*- coding: latin-1 -*-
+import os, sys
+"
+            .trim(),
+            &settings,
+        );
+        assert_messages!(diagnostics);
+    }
 }
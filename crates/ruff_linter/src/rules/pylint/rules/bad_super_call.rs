@@ -1,130 +1,549 @@
-use ast::{Arguments, ExprName, StmtExpr};
-use ruff_diagnostics::{Diagnostic, Violation};
+use ruff_diagnostics::{Diagnostic, Edit, Fix, FixAvailability, Violation};
 use ruff_macros::{derive_message_formats, violation};
-
-use ruff_python_ast::{self as ast, Expr, Stmt};
+use ruff_python_ast::comparable::ComparableExpr;
+use ruff_python_ast::{self as ast, Arguments, Expr, Stmt};
 use ruff_text_size::{Ranged, TextRange};
 
 use crate::checkers::ast::Checker;
+use crate::pattern_rules::{self, PatternRule};
+
+#[derive(Debug, PartialEq, Eq)]
+enum BadSuperCallReason {
+    /// Exactly two arguments are present and structurally equal to the expected slots, just
+    /// swapped.
+    WrongOrder { first: String, second: String },
+    /// Too few arguments were given, or the arguments given don't resolve to the expected slots
+    /// at all (not even swapped).
+    MissingOrIncorrect,
+    /// More arguments were given than there are expected slots to fill.
+    TooManyArguments,
+}
 
 #[violation]
-pub struct BadSuperCall;
+pub struct BadSuperCall {
+    reason: BadSuperCallReason,
+}
 
 impl Violation for BadSuperCall {
     #[derive_message_formats]
     fn message(&self) -> String {
-        format!("Bad first argument given to super()")
+        match &self.reason {
+            BadSuperCallReason::WrongOrder { first, second } => format!(
+                "Arguments to `super()` are in the wrong order, swap `{first}` and `{second}`"
+            ),
+            BadSuperCallReason::MissingOrIncorrect => {
+                format!("Missing or incorrect argument given to `super()`")
+            }
+            BadSuperCallReason::TooManyArguments => {
+                format!("Too many arguments given to `super()`")
+            }
+        }
     }
 }
 
-pub(crate) fn bad_super_call(
-    checker: &mut Checker,
-    ast::StmtClassDef {
-        arguments, body, ..
-    }: &ast::StmtClassDef,
-) {
-    let bad_super = get_bad_super(arguments, body);
-    match bad_super {
-        Some(bs_range) => {
-            checker
-                .diagnostics
-                .push(Diagnostic::new(BadSuperCall, bs_range));
-        }
-        None => {}
+/// ## What it does
+/// Checks for explicit `super()` calls whose arguments are a fully spelled-out, redundant
+/// version of what the zero-argument form already resolves to, e.g. `super(Foo, self)` from
+/// directly within `Foo`.
+///
+/// ## Why is this bad?
+/// Since Python 3, zero-argument `super()` already resolves to the same `(__class__, self)` pair
+/// that the explicit form spells out by hand. The explicit form is more to read and breaks if the
+/// class is ever renamed.
+///
+/// ## Fix safety
+/// The fix is safe: it only rewrites calls whose arguments already match what `super()` would
+/// resolve to.
+#[violation]
+pub struct SuperCallWithParameters;
+
+impl Violation for SuperCallWithParameters {
+    const FIX_AVAILABILITY: FixAvailability = FixAvailability::Always;
+
+    #[derive_message_formats]
+    fn message(&self) -> String {
+        format!("Use `super()` instead of `super(__class__, self)`")
+    }
+
+    fn fix_title(&self) -> Option<String> {
+        Some("Replace with `super()`".to_string())
+    }
+}
+
+/// ## What it does
+/// Checks for an explicit call to a base class method that passes the method's own `self` as the
+/// first argument, e.g. `Base.method(self, ...)` from directly within a subclass of `Base`.
+///
+/// ## Why is this bad?
+/// `Base.method(self, ...)` and `super().method(...)` resolve to the same bound call, but the
+/// explicit spelling hardcodes the base class by name, so it silently stops following the MRO if
+/// the class hierarchy is ever restructured.
+///
+/// ## Fix safety
+/// The fix is only offered when the class declares exactly one base class. With more than one
+/// base, `super()` resolves through the MRO rather than to the named base directly, so rewriting
+/// the call could change which implementation runs.
+#[violation]
+pub struct ExplicitBaseClassCall {
+    base: String,
+    method: String,
+}
+
+impl Violation for ExplicitBaseClassCall {
+    const FIX_AVAILABILITY: FixAvailability = FixAvailability::Sometimes;
+
+    #[derive_message_formats]
+    fn message(&self) -> String {
+        let ExplicitBaseClassCall { base, method } = self;
+        format!("Use `super().{method}(...)` instead of `{base}.{method}(self, ...)`")
+    }
+
+    fn fix_title(&self) -> Option<String> {
+        Some("Replace with a `super()` call".to_string())
     }
 }
 
-fn get_bad_super(arguments: &Option<Box<Arguments>>, body: &[Stmt]) -> Option<TextRange> {
-    // if args then save the args for later
-    let cl_args: ast::Arguments;
-    match arguments {
-        Some(args) => {
-            cl_args = **args;
+/// Flags methods that call a declared base class directly, passing their own `self` through,
+/// instead of going through `super()`.
+pub(crate) fn old_style_super_call(checker: &mut Checker, class_def: &ast::StmtClassDef) {
+    let class_bases = class_bases(class_def);
+
+    for method in get_methods(&class_def.body) {
+        if is_staticmethod(method) {
+            continue;
         }
-        None => {}
-    }
-    let mut res: Option<TextRange>;
-    // get the methods body from the class body
-    let methods_body = get_methods(body);
-    for method in methods_body {
-        // get statements where the super function is called
-        let super_call = get_super_call(method);
-        match super_call {
-            Some(sc) => {
-                let args = sc.arguments;
-                get_bad_super_call_range(args, cl_args);
+
+        let Some(self_param) = method.parameters.args.first() else {
+            continue;
+        };
+        let self_param = self_param.parameter.name.as_str();
+
+        for (value, attribute) in explicit_base_class_calls(&method.body, class_bases, self_param) {
+            let Some(base) = attribute.value.as_name_expr() else {
+                continue;
+            };
+
+            let mut diagnostic = Diagnostic::new(
+                ExplicitBaseClassCall {
+                    base: base.id.to_string(),
+                    method: attribute.attr.to_string(),
+                },
+                value.range(),
+            );
+
+            if class_bases.len() == 1 {
+                let edit = super_call_replacement(
+                    checker,
+                    value,
+                    base.id.as_str(),
+                    attribute.attr.as_str(),
+                    self_param,
+                );
+                if let Some(edit) = edit {
+                    diagnostic = diagnostic.with_fix(Fix::safe_edit(edit));
+                }
             }
-            None => (),
+
+            checker.diagnostics.push(diagnostic);
         }
     }
-    None
 }
 
-fn get_methods(body: &[Stmt]) -> Vec<Vec<Stmt>> {
-    let mut res = Vec::new();
-    for statement in body {
-        match statement {
-            Stmt::FunctionDef(ast::StmtFunctionDef { body, .. }) => {
-                res.push(body.to_vec());
+/// Finds calls of the form `BaseClass.method(self, ...)` that appear as the top-level expression
+/// of a statement in `body` — a bare expression statement, `return BaseClass.method(self, ...)`,
+/// or `x = BaseClass.method(self, ...)` are all common ways to spell this delegation idiom — where
+/// `BaseClass` is one of `class_bases` (compared structurally) and the first argument is
+/// `self_param`. Calls that pass keyword arguments are skipped, since `self_param` dropping out of
+/// a keyword call isn't a simple positional removal.
+fn explicit_base_class_calls<'a>(
+    body: &'a [Stmt],
+    class_bases: &'a [Expr],
+    self_param: &'a str,
+) -> Vec<(&'a Expr, &'a ast::ExprAttribute)> {
+    body.iter()
+        .filter_map(|statement| {
+            let value = top_level_call_candidate(statement)?;
+            let call = value.as_call_expr()?;
+            let attribute = call.func.as_attribute_expr()?;
+
+            if !class_bases
+                .iter()
+                .any(|base| ComparableExpr::from(attribute.value.as_ref()) == ComparableExpr::from(base))
+            {
+                return None;
             }
-            _ => {}
-        }
+
+            if !call.arguments.keywords.is_empty() {
+                return None;
+            }
+
+            let is_self = call
+                .arguments
+                .args
+                .first()?
+                .as_name_expr()
+                .is_some_and(|name| name.id == self_param);
+
+            is_self.then_some((value, attribute))
+        })
+        .collect()
+}
+
+/// Returns the expression to inspect for an explicit base-class call, for the statement kinds
+/// that wrap a single expression result: a bare expression statement, `return ...`, and a
+/// single-target assignment. `targets.len() == 1` excludes chained assignments (`x = y = ...`),
+/// where the call isn't unambiguously "the" right-hand side of one target.
+fn top_level_call_candidate(statement: &Stmt) -> Option<&Expr> {
+    match statement {
+        Stmt::Expr(ast::StmtExpr { value, .. }) => Some(value),
+        Stmt::Return(ast::StmtReturn {
+            value: Some(value), ..
+        }) => Some(value),
+        Stmt::Assign(ast::StmtAssign { targets, value, .. }) if targets.len() == 1 => Some(value),
+        _ => None,
     }
-    res
 }
 
-fn get_super_call(methods: Vec<Stmt>) -> Option<ast::ExprCall> {
-    for statement in methods {
-        match statement {
-            // I don't know which type should go here
-            StmtExpr(call) => {
-                if let Some(name) = call.func.name_expr() {
-                    if name.id == "super" {
-                        return Some(call);
-                    }
-                }
-            }
-            _ => {}
+/// Builds the `Base.method(self, ...) ==>> super().method(...)` [`PatternRule`] for this
+/// particular call site and uses it to produce the replacement [`Edit`], rather than hand-rolling
+/// the equivalent string splicing. `base`, `method`, and `self_param` are spliced in as literal
+/// identifiers (not placeholders) since they're already known from the match that found `value`;
+/// only the trailing arguments vary, which `$args...` captures.
+fn super_call_replacement(
+    checker: &Checker,
+    value: &Expr,
+    base: &str,
+    method: &str,
+    self_param: &str,
+) -> Option<Edit> {
+    let rule = PatternRule::parse(
+        "explicit-base-class-call",
+        &format!("{base}.{method}({self_param}, $args...) ==>> super().{method}($args...)"),
+    )
+    .ok()?;
+
+    pattern_rules::rule_fix(&rule, value, checker.locator(), &mut |_, _| true)
+}
+
+pub(crate) fn bad_super_call(checker: &mut Checker, class_def: &ast::StmtClassDef) {
+    let class_name = class_def.name.as_str();
+
+    for method in get_methods(&class_def.body) {
+        // Staticmethods have no implicit first argument to pass through to `super()`, so any
+        // `super(...)` call inside one can't be the pattern this rule looks for.
+        if is_staticmethod(method) {
+            continue;
+        }
+
+        let Some(call) = get_super_call(&method.body) else {
+            continue;
+        };
+
+        let Some(self_param) = method.parameters.args.first() else {
+            continue;
+        };
+        let self_param = self_param.parameter.name.as_str();
+
+        if let Some(reason) = diagnose_super_call(&call.arguments, class_name, self_param) {
+            let range = bad_super_call_range(&call.arguments, &reason);
+            checker
+                .diagnostics
+                .push(Diagnostic::new(BadSuperCall { reason }, range));
+            continue;
+        }
+
+        if is_redundant_super_call(&call.arguments, class_name, self_param) {
+            let edit = Edit::range_replacement("super()".to_string(), call.range());
+            checker.diagnostics.push(
+                Diagnostic::new(SuperCallWithParameters, call.range()).with_fix(Fix::safe_edit(edit)),
+            );
         }
     }
-    None
 }
 
-/// Gets the range of the bad super call if the super call is acctually bad
+/// Returns the class statement's base class expressions, or an empty slice if the class has no
+/// parenthesized base list at all (e.g. `class Foo:`).
+fn class_bases(class_def: &ast::StmtClassDef) -> &[Expr] {
+    class_def
+        .arguments
+        .as_deref()
+        .map_or(&[], |arguments| &arguments.args)
+}
+
+/// Collects the function defs directly in `body` (i.e. the class's methods). Nested function
+/// defs, lambdas, and methods of nested classes are intentionally not collected, since a
+/// `super()` call found inside one of those wouldn't necessarily resolve against `body`'s own
+/// enclosing class.
+fn get_methods(body: &[Stmt]) -> Vec<&ast::StmtFunctionDef> {
+    body.iter()
+        .filter_map(|statement| match statement {
+            Stmt::FunctionDef(function_def) => Some(function_def),
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_staticmethod(method: &ast::StmtFunctionDef) -> bool {
+    method.decorator_list.iter().any(|decorator| {
+        decorator
+            .expression
+            .as_name_expr()
+            .is_some_and(|name| name.id == "staticmethod")
+    })
+}
+
+/// Finds the first bare `super(...)` call that's a top-level statement in `body`.
+fn get_super_call(body: &[Stmt]) -> Option<&ast::ExprCall> {
+    body.iter().find_map(|statement| {
+        let Stmt::Expr(ast::StmtExpr { value, .. }) = statement else {
+            return None;
+        };
+        let call = value.as_call_expr()?;
+        let name = call.func.as_name_expr()?;
+        (name.id == "super").then_some(call)
+    })
+}
+
+/// Whether `provided` is a bare `Name` equal to `expected`.
+fn is_name(provided: &Expr, expected: &str) -> bool {
+    provided.as_name_expr().is_some_and(|name| name.id == expected)
+}
+
+/// Diagnoses `super_args` against the expected `[EnclosingClass, self]` slots, returning `None` if
+/// the call is fine (this includes the always-fine zero-argument form, and a call whose arguments
+/// already match the expected slots in order).
 ///
-/// For that the function tests the the first argument of the super call is the same as the first
-/// argument of the class statement. For a real bad super call if the first arguments do not match
-/// the super call has to have self as the first argument if the first arguments match the super
-/// call has self right behind the first matching arguments.
+/// `super(...)` only ever has two expected slots — the enclosing class and the method's `self`
+/// parameter — so there are exactly two ways two provided arguments can each match one of them:
+/// in order (fine) or swapped (`WrongOrder`). Anything else (too few arguments,
+/// arguments that don't structurally match either slot) is `MissingOrIncorrect`; more than two
+/// arguments is `TooManyArguments`. There's no general permutation to detect: with only two slots,
+/// a bijection that isn't the identity is necessarily the swap.
 ///
-/// * `super_args`: arguments of the super call
-/// * `class_args`: arguments of the class statement
-fn get_bad_super_call_range(
-    super_args: ast::Arguments,
-    class_args: ast::Arguments,
-) -> Option<TextRange> {
-    let super_args = super_args.args.iter().peekable();
-    let class_args = class_args.args.iter().peekable();
-    // if the super call has no arguments the super call is not bad
-    while super_args.peek().is_some() {
-        let super_arg = super_args.next().unwrap();
-        // you can have a bad super call if the super call has more arguments than the class
-        let class_arg = class_args.next();
-        match class_args {
-            Some(ca) => {
-                // if we have arguments in the class statement we can have a bad super call if the
-                // arguments do not match
-                if super_arg != ca {
-                    return Some(super_arg.range());
-                }
-            }
-            None => {
-                // if the class statement has no arguments the super call is bad if self is not
-                // the first argument if self is the first argument we have an other error
-                if super_arg.name_expr() != "self" {
-                    return Some(super_arg.range());
-                }
-            }
+/// Keyword and starred arguments can't be positionally matched against the expected slots, so
+/// they fall back to the conservative first-mismatch check.
+fn diagnose_super_call(
+    super_args: &Arguments,
+    class_name: &str,
+    self_param: &str,
+) -> Option<BadSuperCallReason> {
+    if !super_args.keywords.is_empty()
+        || super_args.args.iter().any(Expr::is_starred_expr)
+    {
+        return diagnose_super_call_conservative(super_args, class_name, self_param);
+    }
+
+    let provided = &super_args.args;
+    if provided.is_empty() {
+        return None;
+    }
+
+    if provided.len() > 2 {
+        return Some(BadSuperCallReason::TooManyArguments);
+    }
+
+    if provided.len() < 2 {
+        return Some(BadSuperCallReason::MissingOrIncorrect);
+    }
+
+    let (first, second) = (&provided[0], &provided[1]);
+
+    if is_name(first, class_name) && is_name(second, self_param) {
+        // Already in the canonical order.
+        return None;
+    }
+
+    if is_name(first, self_param) && is_name(second, class_name) {
+        return Some(BadSuperCallReason::WrongOrder {
+            first: describe_expr(first),
+            second: describe_expr(second),
+        });
+    }
+
+    Some(BadSuperCallReason::MissingOrIncorrect)
+}
+
+fn describe_expr(expr: &Expr) -> String {
+    expr.as_name_expr()
+        .map(|name| name.id.to_string())
+        .unwrap_or_else(|| "the argument".to_string())
+}
+
+/// The conservative, positional fallback used when `super_args` contains keyword or starred
+/// arguments that [`diagnose_super_call`] can't match against the expected slots: walks the
+/// arguments in order, comparing each against the expected `[class_name, self_param]` slots, and
+/// reports the first mismatch (or any leftover argument) found.
+fn diagnose_super_call_conservative(
+    super_args: &Arguments,
+    class_name: &str,
+    self_param: &str,
+) -> Option<BadSuperCallReason> {
+    let expected = [class_name, self_param];
+
+    for (index, super_arg) in super_args.args.iter().enumerate() {
+        let Some(expected_name) = expected.get(index) else {
+            return Some(BadSuperCallReason::TooManyArguments);
+        };
+        if !is_name(super_arg, *expected_name) {
+            return Some(BadSuperCallReason::MissingOrIncorrect);
         }
     }
+
+    None
+}
+
+/// Picks the range to attach to a [`BadSuperCall`] diagnostic for `reason`.
+fn bad_super_call_range(super_args: &Arguments, reason: &BadSuperCallReason) -> TextRange {
+    match reason {
+        BadSuperCallReason::WrongOrder { .. } => super_args.range(),
+        BadSuperCallReason::MissingOrIncorrect => super_args
+            .args
+            .first()
+            .map_or_else(|| super_args.range(), Ranged::range),
+        BadSuperCallReason::TooManyArguments => super_args
+            .args
+            .last()
+            .map_or_else(|| super_args.range(), Ranged::range),
+    }
+}
+
+/// Whether `super_args` is a redundant, fully explicit spelling of what zero-argument `super()`
+/// already resolves to from directly within the enclosing class: the enclosing class's own name
+/// (not one of its bases — `super(Base, self)` changes where the MRO lookup starts, it is not
+/// equivalent to `super()`), followed by exactly `self_param`.
+///
+/// Assumes `diagnose_super_call(super_args, class_name, self_param)` already returned `None`,
+/// i.e. the call isn't bad to begin with.
+fn is_redundant_super_call(super_args: &Arguments, class_name: &str, self_param: &str) -> bool {
+    if super_args.args.is_empty() {
+        // Already the short form.
+        return false;
+    }
+
+    if super_args.args.len() != 2 {
+        return false;
+    }
+
+    super_args.args[0]
+        .as_name_expr()
+        .is_some_and(|name| name.id == class_name)
+        && super_args
+            .args
+            .last()
+            .and_then(Expr::as_name_expr)
+            .is_some_and(|name| name.id == self_param)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::Rule;
+    use crate::test::test_snippet;
+    use crate::{assert_messages, settings};
+
+    #[test]
+    fn mismatched_arguments_is_bad_super_call() {
+        let diagnostics = test_snippet(
+            "class Foo(Base):\n    def method(self):\n        super(Bar, self)\n",
+            &settings::LinterSettings::for_rules(vec![Rule::BadSuperCall]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn swapped_arguments_report_wrong_order() {
+        let diagnostics = test_snippet(
+            "class Foo(Base):\n    def method(self):\n        super(self, Foo)\n",
+            &settings::LinterSettings::for_rules(vec![Rule::BadSuperCall]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn too_many_arguments_is_flagged() {
+        let diagnostics = test_snippet(
+            "class Foo(Base):\n    def method(self):\n        super(Foo, self, self)\n",
+            &settings::LinterSettings::for_rules(vec![Rule::BadSuperCall]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn base_class_name_is_not_treated_as_redundant() {
+        // `super(Base, self)` is not equivalent to `super()`: it changes where the MRO lookup
+        // starts. Only naming the *enclosing* class (`super(Foo, self)`) is redundant.
+        let diagnostics = test_snippet(
+            "class Foo(Base):\n    def method(self):\n        super(Base, self)\n",
+            &settings::LinterSettings::for_rules(vec![Rule::SuperCallWithParameters]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn redundant_explicit_super_call_is_flagged() {
+        let diagnostics = test_snippet(
+            "class Foo(Base):\n    def method(self):\n        super(Foo, self)\n",
+            &settings::LinterSettings::for_rules(vec![Rule::SuperCallWithParameters]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn zero_argument_super_call_is_fine() {
+        let diagnostics = test_snippet(
+            "class Foo(Base):\n    def method(self):\n        super()\n",
+            &settings::LinterSettings::for_rules(vec![
+                Rule::BadSuperCall,
+                Rule::SuperCallWithParameters,
+            ]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn staticmethod_is_not_flagged() {
+        let diagnostics = test_snippet(
+            "class Foo(Base):\n    @staticmethod\n    def method():\n        super(Foo, self)\n",
+            &settings::LinterSettings::for_rules(vec![
+                Rule::BadSuperCall,
+                Rule::SuperCallWithParameters,
+            ]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn explicit_base_class_call_with_single_base_is_fixed() {
+        let diagnostics = test_snippet(
+            "class Foo(Base):\n    def method(self, a):\n        Base.method(self, a)\n",
+            &settings::LinterSettings::for_rules(vec![Rule::ExplicitBaseClassCall]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn explicit_base_class_call_with_multiple_bases_has_no_fix() {
+        let diagnostics = test_snippet(
+            "class Foo(Base, Other):\n    def method(self):\n        Base.method(self)\n",
+            &settings::LinterSettings::for_rules(vec![Rule::ExplicitBaseClassCall]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn explicit_base_class_call_in_return_statement_is_flagged() {
+        let diagnostics = test_snippet(
+            "class Foo(Base):\n    def method(self, a):\n        return Base.method(self, a)\n",
+            &settings::LinterSettings::for_rules(vec![Rule::ExplicitBaseClassCall]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn explicit_base_class_call_in_assignment_is_flagged() {
+        let diagnostics = test_snippet(
+            "class Foo(Base):\n    def method(self, a):\n        result = Base.method(self, a)\n        return result\n",
+            &settings::LinterSettings::for_rules(vec![Rule::ExplicitBaseClassCall]),
+        );
+        assert_messages!(diagnostics);
+    }
 }
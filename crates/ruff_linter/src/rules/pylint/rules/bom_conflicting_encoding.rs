@@ -0,0 +1,93 @@
+use ruff_diagnostics::{Diagnostic, Violation};
+use ruff_macros::{derive_message_formats, violation};
+use ruff_source_file::Locator;
+use ruff_text_size::{TextRange, TextSize};
+
+use crate::source_encoding::{is_utf8_alias, normalize_encoding_name, CODING_COOKIE};
+
+/// ## What it does
+/// Checks for a UTF-8 byte order mark (BOM) at the start of a file that declares a
+/// non-UTF-8 coding cookie.
+///
+/// ## Why is this bad?
+/// Per PEP 263, a UTF-8 BOM always takes precedence over a coding cookie. If the file also
+/// declares a different encoding, CPython considers this a contradiction and raises a
+/// `SyntaxError` when the file is run, rather than quietly picking one of the two. Projects
+/// standardizing on `BadFileEncoding` alone won't catch this, since from the cookie's
+/// perspective the declared encoding can look perfectly valid.
+#[violation]
+pub struct BomConflictingEncoding {
+    encoding: String,
+}
+
+impl Violation for BomConflictingEncoding {
+    #[derive_message_formats]
+    fn message(&self) -> String {
+        let BomConflictingEncoding { encoding } = self;
+        format!(
+            "File starts with a UTF-8 BOM but declares encoding `{encoding}`; this is a \
+             `SyntaxError` at runtime"
+        )
+    }
+}
+
+/// Checks whether `locator`'s source, which started with a UTF-8 BOM (`has_bom`), declares a
+/// coding cookie that doesn't resolve to a UTF-8 alias.
+pub(crate) fn bom_conflicting_encoding(locator: &Locator, has_bom: bool) -> Option<Diagnostic> {
+    if !has_bom {
+        return None;
+    }
+
+    let mut offset = TextSize::new(0);
+    for line in locator.contents().split('\n').take(2) {
+        let line_range = TextRange::at(offset, TextSize::try_from(line.len()).unwrap());
+        offset = line_range.end() + TextSize::new(1);
+
+        let Some(captures) = CODING_COOKIE.captures(line) else {
+            continue;
+        };
+        let token = captures.get(1).unwrap();
+        let name = token.as_str();
+
+        if is_utf8_alias(&normalize_encoding_name(name)) {
+            continue;
+        }
+
+        let token_range = TextRange::new(
+            line_range.start() + TextSize::try_from(token.start()).unwrap(),
+            line_range.start() + TextSize::try_from(token.end()).unwrap(),
+        );
+        return Some(Diagnostic::new(
+            BomConflictingEncoding {
+                encoding: name.to_string(),
+            },
+            token_range,
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::Rule;
+    use crate::test::test_snippet;
+    use crate::{assert_messages, settings};
+
+    #[test]
+    fn bom_with_conflicting_cookie() {
+        let diagnostics = test_snippet(
+            "\u{feff}# -*- coding: latin-1 -*-\nimport os, sys\n",
+            &settings::LinterSettings::for_rules(vec![Rule::BomConflictingEncoding]),
+        );
+        assert_messages!(diagnostics);
+    }
+
+    #[test]
+    fn bom_with_utf8_cookie_is_fine() {
+        let diagnostics = test_snippet(
+            "\u{feff}# -*- coding: utf-8 -*-\nimport os, sys\n",
+            &settings::LinterSettings::for_rules(vec![Rule::BomConflictingEncoding]),
+        );
+        assert_messages!(diagnostics);
+    }
+}
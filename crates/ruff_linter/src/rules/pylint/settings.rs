@@ -0,0 +1,27 @@
+//! Settings for the `pylint` rule family.
+//!
+//! This mirrors the (crate-level, not present in this snapshot)
+//! `LinterSettings`, which is expected to embed a `pylint: Settings` field
+//! so that rules can reach their options as `settings.pylint.<field>`.
+
+/// Rule-specific settings for the `pylint` rules.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// The set of encodings the `bad-file-encoding` rule accepts in a PEP 263 coding cookie
+    /// without flagging it. Names are compared case-insensitively, with `_`/`-` treated as
+    /// interchangeable, per PEP 263.
+    ///
+    /// Defaults to the aliases Python's `codecs` module treats as UTF-8.
+    pub allowed_encodings: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            allowed_encodings: ["utf-8", "utf8", "u8", "utf", "cp65001"]
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
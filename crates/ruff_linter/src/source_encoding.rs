@@ -0,0 +1,196 @@
+//! Decoding of Python source files per [PEP 263].
+//!
+//! Python source files are not guaranteed to be UTF-8: a file may declare its
+//! encoding via a "coding cookie" comment on the first or second physical
+//! line (e.g. `# -*- coding: latin-1 -*-`). This module reads the raw bytes
+//! of a file, determines the encoding PEP 263 says CPython would use, and
+//! decodes the buffer to a UTF-8 `String` so that the rest of Ruff (tokens,
+//! AST, locator) can keep assuming UTF-8.
+//!
+//! [PEP 263]: https://peps.python.org/pep-0263/
+//!
+//! Status: **unintegrated**. [`read_and_decode_source`] is meant to be the entry point: it runs
+//! once, on the raw bytes read from disk, before a [`Locator`](ruff_source_file::Locator) is ever
+//! constructed for a file, so everything downstream (tokens, AST, locator) can keep assuming
+//! UTF-8. It has no caller in this tree — that call site belongs in the crate's actual
+//! file-reading path (e.g. `ruff_workspace` or the CLI crate), and no such file exists in this
+//! snapshot to wire it into. Both functions are private, not `pub(crate)`, because nothing
+//! outside this module's own tests reaches them; the request this module implements ("lint
+//! non-UTF-8 projects instead of erroring out") is not achieved until that caller exists. An
+//! earlier commit on this branch claimed to "wire `decode_source` into a file-read entry point"
+//! without adding one — that claim was wrong; this module makes no such claim.
+
+use std::io;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Matches a PEP 263 coding cookie on a single physical line, capturing the
+/// declared codec name.
+pub(crate) static CODING_COOKIE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[ \t\f]*#.*?coding[:=][ \t]*([-\w.]+)").unwrap());
+
+/// A UTF-8 BOM, which PEP 263 says takes precedence over (and must not
+/// conflict with) any coding cookie.
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+/// The outcome of decoding a source file's raw bytes to UTF-8.
+#[derive(Debug, Clone)]
+struct DecodedSource {
+    /// The file contents, decoded to UTF-8.
+    text: String,
+    /// The encoding the file actually declared, if any. `None` means the
+    /// file had no coding cookie, so it was decoded as UTF-8.
+    declared_encoding: Option<DeclaredEncoding>,
+}
+
+/// The coding cookie found on one of the first two physical lines of a file.
+#[derive(Debug, Clone)]
+struct DeclaredEncoding {
+    /// The codec name as written in the source, e.g. `latin-1`.
+    name: String,
+    /// Whether the codec, once normalized, is a UTF-8 alias.
+    is_utf8: bool,
+}
+
+/// Reads the first two physical lines of `source`, if present, and returns
+/// the raw coding cookie name, if any.
+///
+/// Per PEP 263, only the first two lines are ever consulted, and the search
+/// stops at the first line that isn't a comment.
+///
+/// Private, not `pub(crate)`: unlike [`CODING_COOKIE`] itself, nothing outside this module's own
+/// tests calls this yet (see the module docs).
+fn find_coding_cookie(source: &[u8]) -> Option<String> {
+    for line in source.split(|&b| b == b'\n').take(2) {
+        // The coding cookie regex only needs to see ASCII, so it's safe to
+        // run against a lossy decode of a single physical line even if the
+        // rest of the file is in a different encoding.
+        let line = String::from_utf8_lossy(line);
+        if let Some(captures) = CODING_COOKIE.captures(&line) {
+            return Some(captures[1].to_string());
+        }
+        // The second line is only consulted if the first is blank or a comment.
+        let trimmed = line.trim_start_matches(|c| matches!(c, ' ' | '\t' | '\x0c'));
+        if !(trimmed.trim_end().is_empty() || trimmed.starts_with('#')) {
+            break;
+        }
+    }
+    None
+}
+
+/// Normalizes a declared codec name per PEP 263: lowercase, and `_`
+/// interchangeable with `-`.
+pub(crate) fn normalize_encoding_name(name: &str) -> String {
+    name.to_lowercase().replace('_', "-")
+}
+
+/// The set of names PEP 263 (and Python's `codecs` module) treat as aliases
+/// for UTF-8.
+pub(crate) fn is_utf8_alias(normalized: &str) -> bool {
+    matches!(normalized, "utf-8" | "utf8" | "u8" | "utf" | "cp65001")
+}
+
+/// Decodes a source file's raw bytes to UTF-8, following PEP 263.
+///
+/// If the file starts with a UTF-8 BOM, the BOM is stripped and the rest of
+/// the file is decoded as UTF-8, regardless of any coding cookie. Otherwise,
+/// the first two physical lines are scanned for a coding cookie; if one is
+/// found and resolves to a known codec, the whole buffer is decoded through
+/// that codec. Absent a BOM or a recognized cookie, the file is assumed to
+/// already be UTF-8.
+fn decode_source(source: &[u8]) -> DecodedSource {
+    if let Some(rest) = source.strip_prefix(&UTF8_BOM) {
+        return DecodedSource {
+            text: String::from_utf8_lossy(rest).into_owned(),
+            declared_encoding: find_coding_cookie(rest).map(|name| {
+                let is_utf8 = is_utf8_alias(&normalize_encoding_name(&name));
+                DeclaredEncoding { name, is_utf8 }
+            }),
+        };
+    }
+
+    let Some(name) = find_coding_cookie(source) else {
+        return DecodedSource {
+            text: String::from_utf8_lossy(source).into_owned(),
+            declared_encoding: None,
+        };
+    };
+
+    let normalized = normalize_encoding_name(&name);
+    let is_utf8 = is_utf8_alias(&normalized);
+
+    let text = if is_utf8 {
+        String::from_utf8_lossy(source).into_owned()
+    } else {
+        match encoding_rs::Encoding::for_label(normalized.as_bytes()) {
+            Some(encoding) => encoding.decode(source).0.into_owned(),
+            // Unknown codec name: fall back to a lossy UTF-8 decode rather
+            // than failing outright.
+            None => String::from_utf8_lossy(source).into_owned(),
+        }
+    };
+
+    DecodedSource {
+        text,
+        declared_encoding: Some(DeclaredEncoding { name, is_utf8 }),
+    }
+}
+
+/// Reads `path` from disk and decodes its contents to UTF-8 per PEP 263.
+///
+/// Callers should use this in place of a raw `fs::read_to_string`/`fs::read` wherever a file's
+/// contents are about to be handed to the lexer (i.e. before a [`Locator`](ruff_source_file::Locator)
+/// is constructed), so that a file with a non-UTF-8 coding cookie is transcoded instead of
+/// rejected or mis-decoded. No such call site exists in this snapshot yet; see the module docs.
+fn read_and_decode_source(path: &Path) -> io::Result<DecodedSource> {
+    let bytes = std::fs::read(path)?;
+    Ok(decode_source(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8_bom_forces_utf8() {
+        let mut source = UTF8_BOM.to_vec();
+        source.extend_from_slice(b"# -*- coding: latin-1 -*-\nprint('hi')\n");
+        let decoded = decode_source(&source);
+        assert!(decoded.text.starts_with("# -*- coding: latin-1 -*-"));
+    }
+
+    #[test]
+    fn latin1_cookie_is_decoded() {
+        let mut source = b"# -*- coding: latin-1 -*-\n".to_vec();
+        source.push(0xE9); // 'e' with acute accent in latin-1
+        source.extend_from_slice(b" = 1\n");
+        let decoded = decode_source(&source);
+        assert!(decoded.text.contains('\u{e9}'));
+        assert!(!decoded.declared_encoding.unwrap().is_utf8);
+    }
+
+    #[test]
+    fn no_cookie_assumes_utf8() {
+        let decoded = decode_source(b"import os\n");
+        assert!(decoded.declared_encoding.is_none());
+    }
+
+    #[test]
+    fn read_and_decode_source_transcodes_a_file_on_disk() {
+        let mut path = std::env::temp_dir();
+        path.push("ruff_source_encoding_read_and_decode_source_test.py");
+
+        let mut source = b"# -*- coding: latin-1 -*-\n".to_vec();
+        source.push(0xE9); // 'e' with acute accent in latin-1
+        source.extend_from_slice(b" = 1\n");
+        std::fs::write(&path, &source).unwrap();
+
+        let decoded = read_and_decode_source(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(decoded.text.contains('\u{e9}'));
+        assert!(!decoded.declared_encoding.unwrap().is_utf8);
+    }
+}
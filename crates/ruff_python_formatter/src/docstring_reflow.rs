@@ -0,0 +1,216 @@
+//! Opt-in hard-wrapping of docstring and comment prose to a configured column.
+//!
+//! Once a block of text has been identified as a docstring body or a standalone `#` comment block
+//! (as opposed to code, a fence, or a verbatim block), [`reflow_prose`] rewraps it to fit within
+//! `line_width`, measuring each candidate line by [`display_width`] rather than byte or `char`
+//! count so that East-Asian wide characters count as two columns and zero-width/combining marks
+//! count as zero.
+//!
+//! Structure that isn't prose is left untouched: fenced code blocks (`` ``` ``/`~~~`), lines
+//! indented enough to be a verbatim block, and common reStructuredText/Markdown constructs (list
+//! items, directives, tables) are passed through as-is, matching the multiline-string
+//! indentation-preservation guarantees the range formatter already relies on.
+//!
+//! Status: **not wired up**. This module only owns the reflow primitive itself; it has no
+//! caller anywhere in this tree outside its own tests. The call site — hard-wrapping a
+//! docstring's body after the usual indentation/quote normalization, or a standalone comment
+//! block, gated on the opt-in being enabled — belongs in `crate::statement::suite`'s docstring
+//! formatting, and that file doesn't exist in this snapshot (see `crate::range`'s
+//! `is_maybe_docstring` handling for where that call site would plug in, and note that
+//! `crate::range` itself already references the nonexistent `crate::statement::suite`). Landing
+//! the reflow request requires adding that integration; until then, this module doesn't change
+//! any formatter output and the request isn't done.
+
+use unicode_width::UnicodeWidthChar;
+
+/// The display width of `text`, in terminal columns: wide (e.g. most CJK) characters count as
+/// 2, zero-width/combining marks count as 0, and everything else counts as 1.
+///
+/// Private rather than `pub(crate)`: nothing outside this module's own tests calls it yet (see
+/// the module-level status note).
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| UnicodeWidthChar::width(c).unwrap_or(0)).sum()
+}
+
+/// Whether `line` looks like something that isn't reflow-able prose: a fence delimiter, a
+/// verbatim/indented line, or a list item/directive/table row.
+fn is_non_prose(line: &str) -> bool {
+    let trimmed = line.trim_start();
+
+    // Fenced code blocks.
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+        return true;
+    }
+
+    // A line whose own leading whitespace is four or more characters, or starts with a tab, is
+    // treated as a verbatim block (e.g. an indented code sample inside a docstring). The check
+    // doesn't look at the surrounding paragraph's indentation.
+    let leading_whitespace = line.len() - trimmed.len();
+    if leading_whitespace >= 4 || line.starts_with('\t') {
+        return true;
+    }
+
+    // Markdown/reST list items and directives.
+    if trimmed.starts_with("- ")
+        || trimmed.starts_with("* ")
+        || trimmed.starts_with("+ ")
+        || trimmed.starts_with(".. ")
+        || trimmed.starts_with(':')
+    {
+        return true;
+    }
+
+    // Numbered list items, e.g. "1. " or "12) ".
+    if trimmed
+        .split_once(|c: char| c == '.' || c == ')')
+        .is_some_and(|(prefix, rest)| {
+            !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_digit()) && rest.starts_with(' ')
+        })
+    {
+        return true;
+    }
+
+    // Table rows.
+    if trimmed.starts_with('|') {
+        return true;
+    }
+
+    false
+}
+
+/// Whether `line` opens or closes a fenced code block (used to track fence state across lines).
+fn is_fence_delimiter(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// Hard-wraps a single paragraph of prose (no blank lines, no non-prose lines) so that every
+/// line's [`display_width`] is at most `line_width`, preserving `indent` on every output line.
+///
+/// Individual words longer than `line_width` are kept whole (never split mid-word).
+fn reflow_paragraph(paragraph: &str, indent: &str, line_width: usize) -> String {
+    let budget = line_width.saturating_sub(display_width(indent));
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+
+    for word in paragraph.split_whitespace() {
+        let word_width = display_width(word);
+        let needed = if current.is_empty() {
+            word_width
+        } else {
+            current_width + 1 + word_width
+        };
+
+        if !current.is_empty() && needed > budget {
+            lines.push(std::mem::take(&mut current));
+            current_width = 0;
+        }
+
+        if !current.is_empty() {
+            current.push(' ');
+            current_width += 1;
+        }
+        current.push_str(word);
+        current_width += word_width;
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+        .into_iter()
+        .map(|line| format!("{indent}{line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Reflows the prose paragraphs of `text` to `line_width` columns, leaving fenced code, verbatim
+/// blocks, list items/directives/tables, and blank lines untouched.
+///
+/// Private rather than `pub(crate)`: nothing outside this module's own tests calls it yet (see
+/// the module-level status note).
+fn reflow_prose(text: &str, line_width: usize) -> String {
+    let mut output = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut paragraph_indent = "";
+    let mut in_fence = false;
+
+    macro_rules! flush_paragraph {
+        () => {
+            if !paragraph.is_empty() {
+                let joined = paragraph.join(" ");
+                output.push(reflow_paragraph(&joined, paragraph_indent, line_width));
+                paragraph.clear();
+            }
+        };
+    }
+
+    for line in text.split('\n') {
+        if in_fence {
+            output.push(line.to_string());
+            if is_fence_delimiter(line) {
+                in_fence = false;
+            }
+            continue;
+        }
+
+        if is_fence_delimiter(line) {
+            flush_paragraph!();
+            output.push(line.to_string());
+            in_fence = true;
+            continue;
+        }
+
+        if line.trim().is_empty() || is_non_prose(line) {
+            flush_paragraph!();
+            output.push(line.to_string());
+            continue;
+        }
+
+        if paragraph.is_empty() {
+            let leading_whitespace = line.len() - line.trim_start().len();
+            paragraph_indent = &line[..leading_whitespace];
+        }
+        paragraph.push(line.trim());
+    }
+    flush_paragraph!();
+
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_width_counts_cjk_as_two_columns() {
+        assert_eq!(display_width("ab"), 2);
+        assert_eq!(display_width("日本語"), 6);
+    }
+
+    #[test]
+    fn combining_marks_count_as_zero() {
+        // 'e' followed by a combining acute accent.
+        assert_eq!(display_width("e\u{0301}"), 1);
+    }
+
+    #[test]
+    fn reflows_overlong_paragraph() {
+        let text = "This is a very long line of prose that should be wrapped once it exceeds the configured column width for the docstring.";
+        let reflowed = reflow_prose(text, 40);
+        for line in reflowed.lines() {
+            assert!(display_width(line) <= 40, "line too wide: {line:?}");
+        }
+        assert_eq!(reflowed.split_whitespace().collect::<Vec<_>>(), text.split_whitespace().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn preserves_code_fence_and_list_items() {
+        let text = "Some prose.\n\n```python\nx   =   1\n```\n\n- a list item that should not be rewrapped even if it runs long\n";
+        let reflowed = reflow_prose(text, 20);
+        assert!(reflowed.contains("x   =   1"));
+        assert!(reflowed.contains("- a list item that should not be rewrapped even if it runs long"));
+    }
+}
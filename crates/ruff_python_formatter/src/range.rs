@@ -2,7 +2,8 @@ use tracing::Level;
 
 use ruff_formatter::printer::SourceMapGeneration;
 use ruff_formatter::{
-    format, FormatContext, FormatError, FormatOptions, IndentStyle, PrintedRange, SourceCode,
+    format, FormatContext, FormatError, FormatOptions, IndentStyle, Printed, PrintedRange,
+    SourceCode,
 };
 use ruff_python_ast::visitor::preorder::{walk_body, PreorderVisitor, TraversalSignal};
 use ruff_python_ast::{AnyNode, AnyNodeRef, Stmt, StmtMatch, StmtTry};
@@ -71,54 +72,447 @@ pub fn format_range(
         return Ok(PrintedRange::new(formatted.into_code(), range));
     }
 
-    let (tokens, comment_ranges) =
-        tokens_and_ranges(source, options.source_type()).map_err(|err| ParseError {
-            offset: err.location(),
-            error: ParseErrorType::Lexical(err.into_error()),
-        })?;
-
     assert_valid_char_boundaries(range, source);
 
-    let module = parse_tokens(tokens, source, options.source_type().as_mode())?;
-    let root = AnyNode::from(module);
-    let source_code = SourceCode::new(source);
-    let comments = Comments::from_ast(root.as_ref(), source_code, &comment_ranges);
+    let parsed = ParsedRangeInput::parse(source, &options)?;
+    Ok(parsed
+        .format_range(source, range, &options)?
+        .unwrap_or_else(PrintedRange::empty))
+}
 
-    let mut context = PyFormatContext::new(
-        options.with_source_map_generation(SourceMapGeneration::Enabled),
-        source,
-        comments,
+/// Formats several, possibly non-contiguous, `ranges` of `source` in a single pass.
+///
+/// Unlike calling [`format_range`] once per range, this tokenizes, parses, and builds the
+/// [`Comments`] a single time and reuses them for every range. Ranges whose enclosing nodes
+/// overlap, are nested, or are adjacent (separated only by whitespace) are coalesced and
+/// formatted against their shared enclosing ancestor once, rather than each being reformatted
+/// from scratch.
+///
+/// `ranges` must be sorted and non-overlapping. Returns one [`PrintedRange`] per input range
+/// that wasn't empty or fully suppressed, in the same relative order as `ranges`, except that
+/// input ranges that narrow to overlapping source ranges (e.g. two selections inside the same
+/// statement) share a single [`PrintedRange`]; the returned ranges are guaranteed
+/// non-overlapping.
+///
+/// # Panics
+/// If `ranges` isn't sorted, or if any two ranges overlap.
+#[tracing::instrument(name = "format_ranges", level = Level::TRACE, skip_all)]
+pub fn format_ranges(
+    source: &str,
+    ranges: &[TextRange],
+    options: PyFormatOptions,
+) -> Result<Vec<PrintedRange>, FormatModuleError> {
+    if ranges.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    assert!(
+        ranges
+            .windows(2)
+            .all(|window| window[0].end() <= window[1].start()),
+        "`ranges` must be sorted and non-overlapping"
     );
 
-    let (enclosing_node, base_indent) = match find_enclosing_node(range, root.as_ref(), &context) {
-        EnclosingNode::Node { node, indent_level } => (node, indent_level),
-        EnclosingNode::Suppressed => {
+    for range in ranges {
+        if source.text_len() < range.end() {
+            return Err(FormatModuleError::FormatError(FormatError::RangeError {
+                input: *range,
+                tree: TextRange::up_to(source.text_len()),
+            }));
+        }
+        assert_valid_char_boundaries(*range, source);
+    }
+
+    let parsed = ParsedRangeInput::parse(source, &options)?;
+
+    // Resolve each range's own innermost enclosing node first.
+    let mut resolved: Vec<(usize, AnyNodeRef<'_>)> = Vec::new();
+    for (index, &range) in ranges.iter().enumerate() {
+        if range.is_empty() {
+            continue;
+        }
+
+        let enclosing_node = match find_enclosing_node(
+            range,
+            parsed.root.as_ref(),
+            &parsed.context_template(source, options.clone()),
+            parsed.detected_indent,
+        ) {
+            EnclosingNode::Node { node, .. } => node,
             // The entire range falls into a suppressed range. There's nothing to format.
-            return Ok(PrintedRange::empty());
+            EnclosingNode::Suppressed => continue,
+        };
+
+        resolved.push((index, enclosing_node));
+    }
+
+    // Coalesce consecutive entries whose enclosing nodes overlap or are adjacent (separated by
+    // nothing but whitespace), so each logical cluster of edits is formatted against a single
+    // shared ancestor instead of producing overlapping `PrintedRange`s.
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (position, &(_, enclosing_node)) in resolved.iter().enumerate() {
+        let touches_previous = groups.last().is_some_and(|group| {
+            let previous_node = resolved[*group.last().unwrap()].1;
+            nodes_touch_or_overlap(previous_node.range(), enclosing_node.range(), source)
+        });
+
+        if touches_previous {
+            groups.last_mut().unwrap().push(position);
+        } else {
+            groups.push(vec![position]);
         }
-    };
+    }
 
-    let narrowed_range = narrow_range(range, enclosing_node, &context);
-    assert_valid_char_boundaries(narrowed_range, source);
+    let mut by_index: Vec<Option<PrintedRange>> = (0..ranges.len()).map(|_| None).collect();
+    for group in groups {
+        // A single-member group keeps its own innermost enclosing node. A multi-member group is
+        // reformatted against the node that encloses all of its members' enclosing nodes, found
+        // the same way a single range's enclosing node is: searching for the deepest node that
+        // fully covers the union of their ranges.
+        let (shared_node, base_indent) = if let [only] = group[..] {
+            match find_enclosing_node(
+                resolved[only].1.range(),
+                parsed.root.as_ref(),
+                &parsed.context_template(source, options.clone()),
+                parsed.detected_indent,
+            ) {
+                EnclosingNode::Node { node, indent_level } => (node, indent_level),
+                EnclosingNode::Suppressed => unreachable!("already resolved to a node above"),
+            }
+        } else {
+            let union_start = group
+                .iter()
+                .map(|&position| resolved[position].1.start())
+                .min()
+                .unwrap();
+            let union_end = group
+                .iter()
+                .map(|&position| resolved[position].1.end())
+                .max()
+                .unwrap();
+
+            match find_enclosing_node(
+                TextRange::new(union_start, union_end),
+                parsed.root.as_ref(),
+                &parsed.context_template(source, options.clone()),
+                parsed.detected_indent,
+            ) {
+                EnclosingNode::Node { node, indent_level } => (node, indent_level),
+                EnclosingNode::Suppressed => unreachable!("already resolved to a node above"),
+            }
+        };
 
-    // Correctly initialize the node level for the blank line rules.
-    if !enclosing_node.is_mod_module() {
-        context.set_node_level(NodeLevel::CompoundStatement);
-        context.set_indent_level(
-            // Plus 1 because `IndentLevel=0` equals the module level.
-            IndentLevel::new(base_indent.saturating_add(1)),
-        );
+        let printed = parsed.format_enclosing_node(source, &options, shared_node, base_indent)?;
+        // `Printed::slice_range` only reads from the source map, so the same `printed` value
+        // can be sliced once per member without reformatting the enclosing node again. Members
+        // whose narrowed ranges overlap are merged so the returned ranges never overlap.
+        let mut merged: Vec<(usize, TextRange)> = Vec::new();
+        for position in group {
+            let (index, _) = resolved[position];
+            let narrowed = narrow_range(
+                ranges[index],
+                shared_node,
+                &parsed.context_template(source, options.clone()),
+                parsed.detected_indent,
+            );
+            match merged.last_mut() {
+                Some((_, last)) if last.end() > narrowed.start() => *last = last.cover(narrowed),
+                _ => merged.push((index, narrowed)),
+            }
+        }
+        for (index, narrowed) in merged {
+            by_index[index] = Some(printed.slice_range(narrowed, source));
+        }
     }
 
-    let formatted = format!(
-        context,
-        [FormatEnclosingNode {
-            root: enclosing_node
-        }]
-    )?;
+    Ok(by_index.into_iter().flatten().collect())
+}
+
+/// Whether `a` and `b` overlap, touch exactly, or are separated only by whitespace — i.e.
+/// formatting them independently could produce two [`PrintedRange`]s that overlap or that cover
+/// logically-adjacent source with no formatted content in between.
+fn nodes_touch_or_overlap(a: TextRange, b: TextRange, source: &str) -> bool {
+    let (first, second) = if a.start() <= b.start() { (a, b) } else { (b, a) };
+
+    if first.end() >= second.start() {
+        return true;
+    }
+
+    source[usize::from(first.end())..usize::from(second.start())]
+        .trim()
+        .is_empty()
+}
+
+/// A minimal line-range replacement, as produced by [`format_range_modified`].
+///
+/// `original_start_line` and `original_removed_count` describe a 1-indexed, exclusive-end line
+/// range in the *original* source (`[original_start_line, original_start_line +
+/// original_removed_count)`) that should be replaced with `inserted_lines`. Lines outside of any
+/// chunk are unchanged, so an editor/LSP client can apply each chunk as a minimal edit instead of
+/// replacing the entire formatted range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModifiedChunk {
+    pub original_start_line: usize,
+    pub original_removed_count: usize,
+    pub inserted_lines: Vec<String>,
+}
+
+/// Like [`format_range`], but instead of returning the full replacement text for the (possibly
+/// expanded) covering range, diffs the formatted output against the corresponding original slice
+/// line-by-line and returns only the lines that actually changed.
+///
+/// This reuses all of `format_range`'s enclosing-node/narrowing machinery; the difference is
+/// purely in how the result is reported, which lets callers (LSP/editor integrations) apply
+/// minimal edits that preserve cursor position and code folding for unchanged lines.
+///
+/// The covering range `format_range` settles on can start or end mid-line — narrowing into a
+/// subexpression (see [`is_formattable_subexpression`]) skips a leading `result = `, and even an
+/// ordinary statement's range starts after its line's leading indentation. [`ModifiedChunk`]s are
+/// always whole-line, so before diffing, the covering range and its formatted text are widened to
+/// full line boundaries (see [`align_to_whole_lines`]) to carry that untouched prefix/suffix text
+/// along; otherwise a chunk replacing that line would silently drop it.
+pub fn format_range_modified(
+    source: &str,
+    range: TextRange,
+    options: PyFormatOptions,
+) -> Result<Vec<ModifiedChunk>, FormatModuleError> {
+    let printed = format_range(source, range, options)?;
+    let (original_range, formatted) =
+        align_to_whole_lines(source, printed.range(), printed.as_code());
+    Ok(diff_modified_chunks(source, original_range, &formatted))
+}
+
+/// Widens `range` to the start of its first line and the end of its last line, and splices the
+/// resulting leading/trailing text (taken verbatim from `source`) onto `formatted` so it lines up
+/// with the widened range.
+///
+/// This is sound only because `range` is expected to be a [`format_range`] covering range: its
+/// start/end are positions [`narrow_range`] guarantees aren't touched by reformatting, so the
+/// source text before `range.start()` and after `range.end()` on their respective lines is
+/// identical whether or not the enclosing node gets reformatted.
+fn align_to_whole_lines(source: &str, range: TextRange, formatted: &str) -> (TextRange, String) {
+    let line_start = source[..usize::from(range.start())]
+        .rfind('\n')
+        .map_or(0, |index| index + 1);
+    let line_end = source[usize::from(range.end())..]
+        .find('\n')
+        .map_or(source.len(), |index| usize::from(range.end()) + index);
+
+    let prefix = &source[line_start..usize::from(range.start())];
+    let suffix = &source[usize::from(range.end())..line_end];
+
+    let mut widened = String::with_capacity(prefix.len() + formatted.len() + suffix.len());
+    widened.push_str(prefix);
+    widened.push_str(formatted);
+    widened.push_str(suffix);
+
+    let widened_range = TextRange::new(
+        TextSize::try_from(line_start).unwrap(),
+        TextSize::try_from(line_end).unwrap(),
+    );
+
+    (widened_range, widened)
+}
+
+/// Diffs the original source lines spanned by `original_range` against `formatted`, using a
+/// line-oriented LCS diff, and collapses the result into the minimal set of [`ModifiedChunk`]s.
+fn diff_modified_chunks(
+    source: &str,
+    original_range: TextRange,
+    formatted: &str,
+) -> Vec<ModifiedChunk> {
+    let original_lines: Vec<&str> = source[original_range].split('\n').collect();
+    let formatted_lines: Vec<&str> = formatted.split('\n').collect();
+
+    // 1-indexed line number of the first line of `original_range` in `source`.
+    let first_line = source[..usize::from(original_range.start())]
+        .matches('\n')
+        .count()
+        + 1;
+
+    let ops = lcs_diff_ops(&original_lines, &formatted_lines);
+
+    let mut chunks = Vec::new();
+    let mut original_index = 0usize;
+    let mut formatted_index = 0usize;
+    let mut i = 0;
+
+    while i < ops.len() {
+        match ops[i] {
+            DiffOp::Equal => {
+                original_index += 1;
+                formatted_index += 1;
+                i += 1;
+            }
+            DiffOp::Remove | DiffOp::Insert => {
+                let start_original = original_index;
+                let start_formatted = formatted_index;
+                let mut removed = 0;
+
+                while i < ops.len() && !matches!(ops[i], DiffOp::Equal) {
+                    match ops[i] {
+                        DiffOp::Remove => {
+                            removed += 1;
+                            original_index += 1;
+                        }
+                        DiffOp::Insert => formatted_index += 1,
+                        DiffOp::Equal => unreachable!(),
+                    }
+                    i += 1;
+                }
+
+                chunks.push(ModifiedChunk {
+                    original_start_line: first_line + start_original,
+                    original_removed_count: removed,
+                    inserted_lines: formatted_lines[start_formatted..formatted_index]
+                        .iter()
+                        .map(|line| (*line).to_string())
+                        .collect(),
+                });
+            }
+        }
+    }
+
+    chunks
+}
 
-    let printed = formatted.print_with_indent(base_indent)?;
-    Ok(printed.slice_range(narrowed_range, source))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffOp {
+    Equal,
+    Remove,
+    Insert,
+}
+
+/// Computes a minimal (longest-common-subsequence based) edit script turning `a` into `b`.
+fn lcs_diff_ops(a: &[&str], b: &[&str]) -> Vec<DiffOp> {
+    let n = a.len();
+    let m = b.len();
+
+    // `lengths[i][j]` is the length of the LCS of `a[i..]` and `b[j..]`.
+    let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            ops.push(DiffOp::Equal);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            ops.push(DiffOp::Remove);
+            i += 1;
+        } else {
+            ops.push(DiffOp::Insert);
+            j += 1;
+        }
+    }
+    ops.extend(std::iter::repeat(DiffOp::Remove).take(n - i));
+    ops.extend(std::iter::repeat(DiffOp::Insert).take(m - j));
+    ops
+}
+
+/// The parsed representation of a source file that's shared across one or more range-formatting
+/// calls, so tokenizing, parsing, and building [`Comments`] only happens once.
+struct ParsedRangeInput<'a> {
+    root: AnyNode<'a>,
+    comments: Comments<'a>,
+    detected_indent: DetectedIndent,
+}
+
+impl<'a> ParsedRangeInput<'a> {
+    fn parse(source: &'a str, options: &PyFormatOptions) -> Result<Self, FormatModuleError> {
+        let (tokens, comment_ranges) =
+            tokens_and_ranges(source, options.source_type()).map_err(|err| ParseError {
+                offset: err.location(),
+                error: ParseErrorType::Lexical(err.into_error()),
+            })?;
+
+        let module = parse_tokens(tokens, source, options.source_type().as_mode())?;
+        let root = AnyNode::from(module);
+        let source_code = SourceCode::new(source);
+        let comments = Comments::from_ast(root.as_ref(), source_code, &comment_ranges);
+
+        // Infer the file's predominant indentation once so that range formatting can fall back
+        // to it, rather than expanding to the entire enclosing body, when a logical line's
+        // leading whitespace doesn't match the configured `IndentStyle`/`IndentWidth`.
+        let detected_indent = DetectedIndent::detect(source, options.indent_width().value());
+
+        Ok(Self {
+            root,
+            comments,
+            detected_indent,
+        })
+    }
+
+    /// A fresh [`PyFormatContext`] for running the `find_enclosing_node`/`narrow_range`
+    /// visitors, which don't mutate formatter-printing state.
+    fn context_template(&self, source: &'a str, options: PyFormatOptions) -> PyFormatContext<'a> {
+        PyFormatContext::new(
+            options.with_source_map_generation(SourceMapGeneration::Enabled),
+            source,
+            self.comments.clone(),
+        )
+    }
+
+    fn format_range(
+        &self,
+        source: &'a str,
+        range: TextRange,
+        options: &PyFormatOptions,
+    ) -> Result<Option<PrintedRange>, FormatModuleError> {
+        let context = self.context_template(source, options.clone());
+        let (enclosing_node, base_indent) =
+            match find_enclosing_node(range, self.root.as_ref(), &context, self.detected_indent) {
+                EnclosingNode::Node { node, indent_level } => (node, indent_level),
+                // The entire range falls into a suppressed range. There's nothing to format.
+                EnclosingNode::Suppressed => return Ok(None),
+            };
+
+        let narrowed_range = narrow_range(range, enclosing_node, &context, self.detected_indent);
+        assert_valid_char_boundaries(narrowed_range, source);
+
+        let printed = self.format_enclosing_node(source, options, enclosing_node, base_indent)?;
+        Ok(Some(printed.slice_range(narrowed_range, source)))
+    }
+
+    /// Formats `enclosing_node` and returns the full printed range (not yet sliced to any
+    /// particular narrowed sub-range).
+    fn format_enclosing_node(
+        &self,
+        source: &'a str,
+        options: &PyFormatOptions,
+        enclosing_node: AnyNodeRef<'a>,
+        base_indent: u16,
+    ) -> Result<Printed, FormatModuleError> {
+        let mut context = self.context_template(source, options.clone());
+
+        // Correctly initialize the node level for the blank line rules.
+        if !enclosing_node.is_mod_module() {
+            context.set_node_level(NodeLevel::CompoundStatement);
+            context.set_indent_level(
+                // Plus 1 because `IndentLevel=0` equals the module level.
+                IndentLevel::new(base_indent.saturating_add(1)),
+            );
+        }
+
+        let formatted = format!(
+            context,
+            [FormatEnclosingNode {
+                root: enclosing_node
+            }]
+        )?;
+
+        Ok(formatted.print_with_indent(base_indent)?)
+    }
 }
 
 /// Finds the node with the minimum covering range of `range`.
@@ -126,12 +520,20 @@ pub fn format_range(
 /// It traverses the tree and returns the deepest node that fully encloses `range`.
 ///
 /// ## Eligible nodes
-/// The search is restricted to nodes that mark the start of a logical line to ensure
+/// The search is primarily restricted to nodes that mark the start of a logical line to ensure
 /// formatting a range results in the same formatting for that logical line as when formatting the entire document.
-/// This property can't be guaranteed when supporting sub-expression formatting because
+/// This property can't be guaranteed for sub-expression formatting in general because
 /// a) Adding parentheses around enclosing expressions can toggle an expression from non-splittable to splittable,
 /// b) formatting a sub-expression has fewer split points than formatting the entire expressions.
 ///
+/// ### Expressions
+/// A narrow, explicitly allow-listed set of expression kinds (see [`is_formattable_subexpression`])
+/// is still eligible: calls, dict/set/list/tuple literals, the comprehension family, `BinOp`/`BoolOp`,
+/// and f-strings. These all carry their own delimiters (parens/brackets/braces) or are parenthesized by
+/// the formatter regardless of context, so formatting them in isolation doesn't run into the two caveats
+/// above. Such a node is formatted with the indentation context of its *enclosing logical line* rather
+/// than its own (often mid-line) column; see [`FindEnclosingNode::current_logical_line_indent`].
+///
 /// ### Possible docstrings
 /// Strings that are suspected to be docstrings are excluded from the search to format the enclosing suite instead
 /// so that the formatter's docstring detection in [`FormatSuite`] correctly detects and formats the docstrings.
@@ -154,8 +556,9 @@ fn find_enclosing_node<'ast>(
     range: TextRange,
     root: AnyNodeRef<'ast>,
     context: &PyFormatContext<'ast>,
+    detected_indent: DetectedIndent,
 ) -> EnclosingNode<'ast> {
-    let mut visitor = FindEnclosingNode::new(range, context);
+    let mut visitor = FindEnclosingNode::new(range, context, detected_indent);
 
     if visitor.enter_node(root).is_traverse() {
         root.visit_preorder(&mut visitor);
@@ -168,28 +571,48 @@ fn find_enclosing_node<'ast>(
 struct FindEnclosingNode<'a, 'ast> {
     range: TextRange,
     context: &'a PyFormatContext<'ast>,
+    detected_indent: DetectedIndent,
 
     /// The, to this point, deepest node that fully encloses `range`.
     closest: EnclosingNode<'ast>,
 
     /// Tracks if the current statement is suppressed
     suppressed: Suppressed,
+
+    /// The number of indent-scope-opening ancestors (see [`opens_indent_scope`]) visited so far
+    /// on the path from the root to the node currently being entered. Used as a structural
+    /// fallback for [`indent_level`] when the string-based scan can't make sense of a node's
+    /// leading whitespace.
+    scope_depth: u16,
+
+    /// The indent level computed for the closest logical-line ancestor visited so far. Sub-
+    /// expressions (see [`is_formattable_subexpression`]) reuse this rather than computing their
+    /// own, since their own start offset is usually mid-line and carries no indentation signal.
+    current_logical_line_indent: u16,
 }
 
 impl<'a, 'ast> FindEnclosingNode<'a, 'ast> {
-    fn new(range: TextRange, context: &'a PyFormatContext<'ast>) -> Self {
+    fn new(
+        range: TextRange,
+        context: &'a PyFormatContext<'ast>,
+        detected_indent: DetectedIndent,
+    ) -> Self {
         Self {
             range,
             context,
+            detected_indent,
             suppressed: Suppressed::No,
             closest: EnclosingNode::Suppressed,
+            scope_depth: 0,
+            current_logical_line_indent: 0,
         }
     }
 }
 
 impl<'ast> PreorderVisitor<'ast> for FindEnclosingNode<'_, 'ast> {
     fn enter_node(&mut self, node: AnyNodeRef<'ast>) -> TraversalSignal {
-        if !(is_logical_line(node) || node.is_mod_module()) {
+        let is_subexpression = is_formattable_subexpression(node);
+        if !(is_logical_line(node) || node.is_mod_module() || is_subexpression) {
             return TraversalSignal::Skip;
         }
 
@@ -211,9 +634,26 @@ impl<'ast> PreorderVisitor<'ast> for FindEnclosingNode<'_, 'ast> {
             return TraversalSignal::Skip;
         }
 
+        if is_subexpression {
+            // Reuse the enclosing logical line's indentation context rather than computing one
+            // from this node's own (usually mid-line) start offset; see `is_formattable_subexpression`.
+            self.closest = EnclosingNode::Node {
+                node,
+                indent_level: self.current_logical_line_indent,
+            };
+            return TraversalSignal::Traverse;
+        }
+
         // Don't pick potential docstrings as the closest enclosing node because `suite.rs` than fails to identify them as
         // docstrings and docstring formatting won't kick in.
         // Format the enclosing node instead and slice the formatted docstring from the result.
+        //
+        // When the opt-in docstring/comment reflow is enabled, `suite.rs`'s docstring formatting
+        // is expected to hard-wrap the body's prose via `crate::docstring_reflow::reflow_prose`
+        // after the usual indentation/quote normalization (see that module's docs: the call site
+        // itself lives in `suite.rs`, not here, and isn't part of this snapshot); the
+        // indentation-preservation guarantees here are unaffected either way, since reflow only
+        // touches prose lines, never the surrounding whitespace.
         let is_maybe_docstring = node.as_stmt_expr().is_some_and(|stmt| {
             DocstringStmt::is_docstring_statement(stmt, self.context.options().source_type())
         });
@@ -222,21 +662,36 @@ impl<'ast> PreorderVisitor<'ast> for FindEnclosingNode<'_, 'ast> {
             return TraversalSignal::Skip;
         }
 
-        // Only computing the count here is sufficient because each enclosing node ensures that it has the necessary indent
-        // or we don't traverse otherwise.
-        let Some(indent_level) =
-            indent_level(node.start(), self.context.source(), self.context.options())
-        else {
-            // Non standard indent or a simple-statement body of a compound statement, format the enclosing node
-            return TraversalSignal::Skip;
-        };
+        // Prefer the string-based scan, which is exact, but fall back to the structural ancestor
+        // count (see `opens_indent_scope`) when the leading whitespace doesn't conform to either
+        // the configured or the detected indentation (e.g. mixed tabs/spaces, or an
+        // over-indented block). This lets range formatting proceed on an oddly-but-validly
+        // indented file instead of always widening to the enclosing body.
+        let indent_level = indent_level(
+            node.start(),
+            self.context.source(),
+            self.context.options(),
+            self.detected_indent,
+        )
+        .unwrap_or(self.scope_depth);
 
         self.closest = EnclosingNode::Node { node, indent_level };
+        self.current_logical_line_indent = indent_level;
+
+        if opens_indent_scope(node) {
+            self.scope_depth += 1;
+        }
 
         TraversalSignal::Traverse
     }
 
     fn leave_node(&mut self, node: AnyNodeRef<'ast>) {
+        // `leave_node` also runs for nodes `enter_node` skipped; only those enclosing `range`
+        // were counted on the way in.
+        if opens_indent_scope(node) && node.range().contains_range(self.range) {
+            self.scope_depth = self.scope_depth.saturating_sub(1);
+        }
+
         if node.is_statement() {
             let trailing_comments = self.context.comments().trailing(node);
             // Update the suppressed state for the next statement.
@@ -302,7 +757,14 @@ fn narrow_range(
     range: TextRange,
     enclosing_node: AnyNodeRef,
     context: &PyFormatContext,
+    detected_indent: DetectedIndent,
 ) -> TextRange {
+    // A sub-expression usually starts mid-line, so it has no indentation to anchor on, and
+    // `NarrowRange` only visits logical lines: its whole range is the narrowest formattable one.
+    if is_formattable_subexpression(enclosing_node) {
+        return enclosing_node.range();
+    }
+
     let locator = context.locator();
     let enclosing_indent = indentation_at_offset(enclosing_node.start(), &locator)
         .expect("Expected enclosing to never be a same line body statement.");
@@ -310,6 +772,7 @@ fn narrow_range(
     let mut visitor = NarrowRange {
         context,
         range,
+        detected_indent,
 
         narrowed_start: enclosing_node.start(),
         narrowed_end: enclosing_node.end(),
@@ -329,6 +792,7 @@ fn narrow_range(
 
 struct NarrowRange<'a> {
     context: &'a PyFormatContext<'a>,
+    detected_indent: DetectedIndent,
 
     // The range to format
     range: TextRange,
@@ -522,18 +986,20 @@ impl NarrowRange<'_> {
                 let expected_indents = self.level;
 
                 // Each level must always add one level of indent. That's why an empty relative indent to the parent node tells us that the enclosing node is the Module.
-                let has_expected_indentation = match self.context.options().indent_style() {
-                    IndentStyle::Tab => {
-                        relative_indent.len() == expected_indents
-                            && relative_indent.chars().all(|c| c == '\t')
-                    }
-                    IndentStyle::Space => {
-                        relative_indent.len()
-                            == expected_indents
-                                * self.context.options().indent_width().value() as usize
-                            && relative_indent.chars().all(|c| c == ' ')
-                    }
-                };
+                // Try the configured style first, and fall back to the file's detected
+                // indentation so that uniformly-but-unconventionally indented files can still
+                // be narrowed instead of expanding to the entire body.
+                let has_expected_indentation = matches_indentation(
+                    relative_indent,
+                    self.context.options().indent_style(),
+                    self.context.options().indent_width().value(),
+                    expected_indents,
+                ) || matches_indentation(
+                    relative_indent,
+                    self.detected_indent.style,
+                    self.detected_indent.width,
+                    expected_indents,
+                );
 
                 if !has_expected_indentation {
                     return None;
@@ -557,13 +1023,99 @@ impl NarrowRange<'_> {
     }
 }
 
+/// How a node participates in the logical-line/indent-scope structure that the enclosing-node
+/// search ([`is_logical_line`]), [`FormatEnclosingNode`]'s dispatch, and the structural indent
+/// fallback ([`opens_indent_scope`]) all need to agree on. `None` means `node` is none of these
+/// things (an ordinary expression, a pattern, an argument list, ...).
+///
+/// This is the single source of truth for that classification: [`is_logical_line`] and
+/// [`opens_indent_scope`] are both thin wrappers around it, so the three call sites can't drift
+/// out of sync the way three independently hand-maintained node lists could.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ScopeKind {
+    /// A statement that introduces a new suite whose body is indented one level deeper than the
+    /// statement itself: function/class defs, `for`, `while`, `if`, `with`, `try`, `match`.
+    OpensSuite,
+
+    /// A statement that's formatted as its own logical line but doesn't introduce an indent
+    /// scope of its own, e.g. `return`, `x = 1`, `import os`.
+    Standalone,
+
+    /// A fragment of a compound statement that's formatted as its own logical line but belongs
+    /// to an enclosing statement rather than standing alone: `elif`/`else`, `except`, `case`, and
+    /// decorators. `elif`/`else` and `except`/`case` also open a suite of their own, hence why
+    /// [`opens_indent_scope`] treats them the same as `OpensSuite`.
+    ClauseFragment,
+}
+
+/// Classifies `node` per [`ScopeKind`], or `None` if it's neither a logical line nor an
+/// indent-scope boundary by itself.
+pub(crate) const fn node_indent_scope(node: AnyNodeRef) -> Option<ScopeKind> {
+    match node {
+        AnyNodeRef::StmtFunctionDef(_)
+        | AnyNodeRef::StmtClassDef(_)
+        | AnyNodeRef::StmtFor(_)
+        | AnyNodeRef::StmtWhile(_)
+        | AnyNodeRef::StmtIf(_)
+        | AnyNodeRef::StmtWith(_)
+        | AnyNodeRef::StmtTry(_)
+        | AnyNodeRef::StmtMatch(_) => Some(ScopeKind::OpensSuite),
+
+        AnyNodeRef::ElifElseClause(_)
+        | AnyNodeRef::ExceptHandlerExceptHandler(_)
+        | AnyNodeRef::MatchCase(_)
+        | AnyNodeRef::Decorator(_) => Some(ScopeKind::ClauseFragment),
+
+        _ if node.is_statement() => Some(ScopeKind::Standalone),
+
+        _ => None,
+    }
+}
+
 pub(crate) const fn is_logical_line(node: AnyNodeRef) -> bool {
-    // Make sure to update [`FormatEnclosingLine`] when changing this.
-    node.is_statement()
-        || node.is_decorator()
-        || node.is_except_handler()
-        || node.is_elif_else_clause()
-        || node.is_match_case()
+    node_indent_scope(node).is_some()
+}
+
+/// Whether `node` opens a new suite/indent scope, i.e. its body is indented one level deeper
+/// than `node` itself.
+///
+/// `elif`/`else`, `except`, and `case` clauses are [`ScopeKind::ClauseFragment`] rather than
+/// [`ScopeKind::OpensSuite`] (they don't stand alone as statements), but their own bodies are
+/// still indented one level deeper, so they count here too.
+///
+/// Used to derive a structural indentation level by counting scope-opening ancestors, as a
+/// fallback for [`indent_level`] when the leading whitespace can't be parsed as a plain multiple
+/// of the configured or detected indent.
+const fn opens_indent_scope(node: AnyNodeRef) -> bool {
+    matches!(
+        node_indent_scope(node),
+        Some(ScopeKind::OpensSuite | ScopeKind::ClauseFragment)
+    ) && !matches!(node, AnyNodeRef::Decorator(_))
+}
+
+/// Whether `node` is an expression that range formatting may pick as the enclosing node in its
+/// own right, rather than only ever widening to its enclosing logical line.
+///
+/// Restricted to expression kinds that carry their own delimiters (parens/brackets/braces) or are
+/// always parenthesized by the formatter regardless of surrounding context, so formatting them in
+/// isolation can't change their own splittability the way an arbitrary sub-expression might (see
+/// the "Eligible nodes" section on [`find_enclosing_node`]'s docs).
+const fn is_formattable_subexpression(node: AnyNodeRef) -> bool {
+    matches!(
+        node,
+        AnyNodeRef::ExprCall(_)
+            | AnyNodeRef::ExprDict(_)
+            | AnyNodeRef::ExprSet(_)
+            | AnyNodeRef::ExprList(_)
+            | AnyNodeRef::ExprTuple(_)
+            | AnyNodeRef::ExprListComp(_)
+            | AnyNodeRef::ExprSetComp(_)
+            | AnyNodeRef::ExprDictComp(_)
+            | AnyNodeRef::ExprGeneratorExp(_)
+            | AnyNodeRef::ExprBinOp(_)
+            | AnyNodeRef::ExprBoolOp(_)
+            | AnyNodeRef::ExprFString(_)
+    )
 }
 
 #[derive(Debug)]
@@ -612,8 +1164,19 @@ struct FormatEnclosingNode<'a> {
 
 impl Format<PyFormatContext<'_>> for FormatEnclosingNode<'_> {
     fn fmt(&self, f: &mut Formatter<PyFormatContext<'_>>) -> FormatResult<()> {
-        // Note: It's important that this supports formatting all nodes for which `is_logical_line`
-        // returns + the root `Mod` nodes.
+        // This must support formatting every node `node_indent_scope` classifies as a logical
+        // line, every `is_formattable_subexpression` node, and the root `Mod` nodes; the debug
+        // assertion below catches new variants that `find_enclosing_node` was taught to return
+        // but that this dispatch wasn't updated to format.
+        debug_assert!(
+            self.root.is_mod_module()
+                || self.root.is_mod_expression()
+                || node_indent_scope(self.root).is_some()
+                || is_formattable_subexpression(self.root),
+            "FormatEnclosingNode::fmt received a node outside of its supported set: {:?}",
+            self.root
+        );
+
         match self.root {
             AnyNodeRef::ModModule(node) => node.format().fmt(f),
             AnyNodeRef::ModExpression(node) => node.format().fmt(f),
@@ -647,27 +1210,32 @@ impl Format<PyFormatContext<'_>> for FormatEnclosingNode<'_> {
             AnyNodeRef::Decorator(node) => node.format().fmt(f),
             AnyNodeRef::ElifElseClause(node) => node.format().fmt(f),
 
-            AnyNodeRef::ExprBoolOp(_)
-            | AnyNodeRef::ExprNamedExpr(_)
-            | AnyNodeRef::ExprBinOp(_)
+            // Formattable in isolation: see `is_formattable_subexpression`. Formatted with the
+            // indentation context of their enclosing logical line, not their own start column.
+            AnyNodeRef::ExprCall(node) => node.format().fmt(f),
+            AnyNodeRef::ExprDict(node) => node.format().fmt(f),
+            AnyNodeRef::ExprSet(node) => node.format().fmt(f),
+            AnyNodeRef::ExprList(node) => node.format().fmt(f),
+            AnyNodeRef::ExprTuple(node) => node.format().fmt(f),
+            AnyNodeRef::ExprListComp(node) => node.format().fmt(f),
+            AnyNodeRef::ExprSetComp(node) => node.format().fmt(f),
+            AnyNodeRef::ExprDictComp(node) => node.format().fmt(f),
+            AnyNodeRef::ExprGeneratorExp(node) => node.format().fmt(f),
+            AnyNodeRef::ExprBinOp(node) => node.format().fmt(f),
+            AnyNodeRef::ExprBoolOp(node) => node.format().fmt(f),
+            AnyNodeRef::ExprFString(node) => node.format().fmt(f),
+
+            AnyNodeRef::ExprNamedExpr(_)
             | AnyNodeRef::ExprUnaryOp(_)
             | AnyNodeRef::ExprLambda(_)
             | AnyNodeRef::ExprIfExp(_)
-            | AnyNodeRef::ExprDict(_)
-            | AnyNodeRef::ExprSet(_)
-            | AnyNodeRef::ExprListComp(_)
-            | AnyNodeRef::ExprSetComp(_)
-            | AnyNodeRef::ExprDictComp(_)
-            | AnyNodeRef::ExprGeneratorExp(_)
             | AnyNodeRef::ExprAwait(_)
             | AnyNodeRef::ExprYield(_)
             | AnyNodeRef::ExprYieldFrom(_)
             | AnyNodeRef::ExprCompare(_)
-            | AnyNodeRef::ExprCall(_)
             | AnyNodeRef::FStringExpressionElement(_)
             | AnyNodeRef::FStringLiteralElement(_)
             | AnyNodeRef::FStringFormatSpec(_)
-            | AnyNodeRef::ExprFString(_)
             | AnyNodeRef::ExprStringLiteral(_)
             | AnyNodeRef::ExprBytesLiteral(_)
             | AnyNodeRef::ExprNumberLiteral(_)
@@ -678,8 +1246,6 @@ impl Format<PyFormatContext<'_>> for FormatEnclosingNode<'_> {
             | AnyNodeRef::ExprSubscript(_)
             | AnyNodeRef::ExprStarred(_)
             | AnyNodeRef::ExprName(_)
-            | AnyNodeRef::ExprList(_)
-            | AnyNodeRef::ExprTuple(_)
             | AnyNodeRef::ExprSlice(_)
             | AnyNodeRef::ExprIpyEscapeCommand(_)
             | AnyNodeRef::FString(_)
@@ -713,19 +1279,60 @@ impl Format<PyFormatContext<'_>> for FormatEnclosingNode<'_> {
     }
 }
 
-/// Computes the level of indentation for `indentation` when using the configured [`IndentStyle`] and [`IndentWidth`].
+/// Computes the level of indentation for `indentation` when using the configured [`IndentStyle`] and [`IndentWidth`],
+/// falling back to `detected_indent` if the configured style doesn't match.
 ///
-/// Returns `None` if the indentation doesn't conform to the configured [`IndentStyle`] and [`IndentWidth`].
+/// Returns `None` if the indentation conforms to neither.
 ///
 /// # Panics
 /// If `offset` is outside of `source`.
-fn indent_level(offset: TextSize, source: &str, options: &PyFormatOptions) -> Option<u16> {
+fn indent_level(
+    offset: TextSize,
+    source: &str,
+    options: &PyFormatOptions,
+    detected_indent: DetectedIndent,
+) -> Option<u16> {
     let locator = Locator::new(source);
     let indentation = indentation_at_offset(offset, &locator)?;
 
-    let level = match options.indent_style() {
+    // Status: the "smart tabs" opt-in (tabs express nesting depth, a trailing run of spaces
+    // expresses intra-line alignment, mirroring the block-indentation/visual-indentation
+    // distinction rustfmt draws) is NOT exposed anywhere a caller can reach it. `indent_level_with`
+    // below correctly implements the algorithm and is unit-tested directly, but nothing in this
+    // snapshot can pass `smart_tabs = true` through the public API, because that requires a
+    // `tab_alignment`-equivalent field on `PyFormatOptions`, and that type isn't defined in this
+    // snapshot for this module to extend. Hardcoded off so every caller keeps today's behavior.
+    // This does not satisfy the "expose this as an opt-in" part of the request; it only keeps
+    // the crate from calling a method that doesn't exist.
+    let smart_tabs = false;
+
+    indent_level_with(
+        indentation,
+        options.indent_style(),
+        options.indent_width().value(),
+        smart_tabs,
+    )
+    .or_else(|| indent_level_with(indentation, detected_indent.style, detected_indent.width, smart_tabs))
+}
+
+/// Computes the level of `indentation` under a single `(style, width)` pair, or `None` if
+/// `indentation` doesn't conform to it.
+///
+/// When `style` is [`IndentStyle::Tab`] and `smart_tabs` is enabled, only the leading run of
+/// `'\t'` characters counts toward the indentation level; a trailing run of spaces is accepted as
+/// alignment and ignored, but a tab following a space is rejected (interleaved tabs-after-spaces
+/// isn't a visual-indent convention this recognizes).
+fn indent_level_with(indentation: &str, style: IndentStyle, width: u8, smart_tabs: bool) -> Option<u16> {
+    let level = match style {
         IndentStyle::Tab => {
-            if indentation.chars().all(|c| c == '\t') {
+            if smart_tabs {
+                let leading_tabs = indentation.chars().take_while(|&c| c == '\t').count();
+                if indentation[leading_tabs..].chars().all(|c| c == ' ') {
+                    Some(leading_tabs)
+                } else {
+                    None
+                }
+            } else if indentation.chars().all(|c| c == '\t') {
                 Some(indentation.len())
             } else {
                 None
@@ -733,8 +1340,11 @@ fn indent_level(offset: TextSize, source: &str, options: &PyFormatOptions) -> Op
         }
 
         IndentStyle::Space => {
-            let indent_width = options.indent_width().value() as usize;
-            if indentation.chars().all(|c| c == ' ') && indentation.len() % indent_width == 0 {
+            let indent_width = width as usize;
+            if indent_width != 0
+                && indentation.chars().all(|c| c == ' ')
+                && indentation.len() % indent_width == 0
+            {
                 Some(indentation.len() / indent_width)
             } else {
                 None
@@ -744,3 +1354,458 @@ fn indent_level(offset: TextSize, source: &str, options: &PyFormatOptions) -> Op
 
     level.map(|level| u16::try_from(level).unwrap_or(u16::MAX))
 }
+
+/// Whether `relative_indent` expresses exactly `expected_indents` levels of indentation under
+/// `(style, width)`.
+fn matches_indentation(
+    relative_indent: &str,
+    style: IndentStyle,
+    width: u8,
+    expected_indents: usize,
+) -> bool {
+    match style {
+        IndentStyle::Tab => {
+            relative_indent.len() == expected_indents && relative_indent.chars().all(|c| c == '\t')
+        }
+        IndentStyle::Space => {
+            relative_indent.len() == expected_indents * width as usize
+                && relative_indent.chars().all(|c| c == ' ')
+        }
+    }
+}
+
+/// The file's predominant indentation style and width, inferred by scanning the source.
+///
+/// Used as a fallback wherever a node's leading whitespace doesn't match the configured
+/// [`IndentStyle`]/[`IndentWidth`], so files that consistently use a different-but-uniform
+/// indentation (e.g. 2 spaces when the configured width is 4) can still be range-formatted
+/// instead of always expanding to the entire enclosing body.
+#[derive(Debug, Clone, Copy)]
+struct DetectedIndent {
+    style: IndentStyle,
+    width: u8,
+}
+
+impl DetectedIndent {
+    /// The minimum number of indentation-delta samples required before trusting the detected
+    /// width over the configured one.
+    const MIN_SAMPLES: u32 = 3;
+
+    /// Scans `source` for its dominant indentation.
+    ///
+    /// Builds a histogram over the positive change in leading-whitespace length between
+    /// consecutive non-blank lines, bucketed into widths 1..=8, and separately counts how many
+    /// non-blank lines are tab-indented. If tab-indented lines dominate, the file is considered
+    /// tab-indented; otherwise the histogram bucket with the highest count is used as the
+    /// detected space width (ties favor the smaller width). Falls back to `configured_width`
+    /// when there isn't enough signal to detect anything.
+    fn detect(source: &str, configured_width: u8) -> Self {
+        let mut buckets = [0u32; 8];
+        let mut tab_lines = 0u32;
+        let mut space_lines = 0u32;
+        let mut previous_indent: Option<usize> = None;
+
+        for line in source.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let leading_tabs = line.bytes().take_while(|&b| b == b'\t').count();
+            let leading_spaces = line.bytes().skip(leading_tabs).take_while(|&b| b == b' ').count();
+            let indent = leading_tabs + leading_spaces;
+
+            if leading_tabs > 0 {
+                tab_lines += 1;
+            } else if leading_spaces > 0 {
+                space_lines += 1;
+            }
+
+            if let Some(previous) = previous_indent {
+                if indent > previous {
+                    if let Some(bucket) = (indent - previous).checked_sub(1).filter(|i| *i < 8) {
+                        buckets[bucket] += 1;
+                    }
+                }
+            }
+            previous_indent = Some(indent);
+        }
+
+        if tab_lines > space_lines {
+            return Self {
+                style: IndentStyle::Tab,
+                width: configured_width,
+            };
+        }
+
+        if buckets.iter().sum::<u32>() < Self::MIN_SAMPLES {
+            return Self {
+                style: IndentStyle::Space,
+                width: configured_width,
+            };
+        }
+
+        let mut best_width = configured_width;
+        let mut best_count = 0;
+        for (index, &count) in buckets.iter().enumerate() {
+            if count > best_count {
+                best_count = count;
+                best_width = (index + 1) as u8;
+            }
+        }
+
+        Self {
+            style: IndentStyle::Space,
+            width: best_width,
+        }
+    }
+}
+
+#[cfg(test)]
+mod detected_indent_tests {
+    use super::DetectedIndent;
+    use ruff_formatter::IndentStyle;
+
+    #[test]
+    fn detects_two_space_indent() {
+        let source = "if True:\n  a = 1\n  if True:\n    b = 2\n    if True:\n      c = 3\n";
+        let detected = DetectedIndent::detect(source, 4);
+        assert_eq!(detected.style, IndentStyle::Space);
+        assert_eq!(detected.width, 2);
+    }
+
+    #[test]
+    fn detects_tabs() {
+        let source = "if True:\n\ta = 1\n\tif True:\n\t\tb = 2\n";
+        let detected = DetectedIndent::detect(source, 4);
+        assert_eq!(detected.style, IndentStyle::Tab);
+    }
+
+    #[test]
+    fn falls_back_to_configured_width_without_signal() {
+        let detected = DetectedIndent::detect("a = 1\n", 4);
+        assert_eq!(detected.style, IndentStyle::Space);
+        assert_eq!(detected.width, 4);
+    }
+}
+
+#[cfg(test)]
+mod modified_chunk_tests {
+    use super::{diff_modified_chunks, ModifiedChunk};
+    use ruff_text_size::{TextLen, TextRange};
+
+    #[test]
+    fn only_the_changed_line_is_reported() {
+        let source = "a = 1\nb    =2\nc = 3\n";
+        let original_range = TextRange::up_to(source.text_len());
+        let formatted = "a = 1\nb = 2\nc = 3\n";
+
+        let chunks = diff_modified_chunks(source, original_range, formatted);
+        assert_eq!(
+            chunks,
+            vec![ModifiedChunk {
+                original_start_line: 2,
+                original_removed_count: 1,
+                inserted_lines: vec!["b = 2".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn identical_text_produces_no_chunks() {
+        let source = "a = 1\nb = 2\n";
+        let original_range = TextRange::up_to(source.text_len());
+        let chunks = diff_modified_chunks(source, original_range, source);
+        assert_eq!(chunks, Vec::new());
+    }
+
+    #[test]
+    fn mid_line_narrowed_range_keeps_its_line_prefix() {
+        // `format_range` narrows into the call expression, skipping the `result = ` prefix (see
+        // `narrows_to_a_call_expression`); `format_range_modified` must still report a chunk whose
+        // `inserted_lines` reconstruct the *entire* source line, not just the reformatted part,
+        // otherwise a whole-line-replacement client would drop the prefix.
+        use super::format_range_modified;
+        use crate::PyFormatOptions;
+
+        fn range_of(source: &str, needle: &str) -> TextRange {
+            let start = source.find(needle).expect("needle not found in source");
+            TextRange::at(
+                ruff_text_size::TextSize::try_from(start).unwrap(),
+                ruff_text_size::TextSize::try_from(needle.len()).unwrap(),
+            )
+        }
+
+        let source = "result = some_call(1,2,3)\n";
+        let range = range_of(source, "some_call(1,2,3)");
+
+        let chunks = format_range_modified(source, range, PyFormatOptions::default()).unwrap();
+        assert_eq!(
+            chunks,
+            vec![ModifiedChunk {
+                original_start_line: 1,
+                original_removed_count: 1,
+                inserted_lines: vec!["result = some_call(1, 2, 3)".to_string()],
+            }]
+        );
+    }
+}
+
+#[cfg(test)]
+mod smart_tabs_tests {
+    use super::indent_level_with;
+    use ruff_formatter::IndentStyle;
+
+    #[test]
+    fn leading_tabs_with_trailing_alignment_spaces() {
+        // Two levels of nesting (two leading tabs), followed by four spaces of alignment.
+        assert_eq!(
+            indent_level_with("\t\t    ", IndentStyle::Tab, 4, true),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn pure_tabs_still_works_with_smart_tabs_enabled() {
+        assert_eq!(indent_level_with("\t\t\t", IndentStyle::Tab, 4, true), Some(3));
+    }
+
+    #[test]
+    fn space_before_tab_is_rejected() {
+        // A tab following a space isn't a recognized visual-indent convention.
+        assert_eq!(indent_level_with(" \t", IndentStyle::Tab, 4, true), None);
+    }
+
+    #[test]
+    fn trailing_spaces_rejected_without_smart_tabs() {
+        // The opt-in is off by default: a tab run followed by spaces still doesn't conform.
+        assert_eq!(indent_level_with("\t\t    ", IndentStyle::Tab, 4, false), None);
+    }
+}
+
+#[cfg(test)]
+mod scope_kind_tests {
+    use super::{is_formattable_subexpression, is_logical_line, node_indent_scope, opens_indent_scope, ScopeKind};
+    use ruff_python_ast::{AnyNodeRef, ModModule, Stmt};
+    use ruff_python_parser::parse_module;
+
+    fn parse(source: &str) -> ModModule {
+        parse_module(source).expect("valid source").into_syntax()
+    }
+
+    #[test]
+    fn opens_suite_statement_is_a_logical_line_that_opens_scope() {
+        let module = parse("def foo():\n    pass\n");
+        let any = AnyNodeRef::from(&module.body[0]);
+        assert_eq!(node_indent_scope(any), Some(ScopeKind::OpensSuite));
+        assert!(is_logical_line(any));
+        assert!(opens_indent_scope(any));
+    }
+
+    #[test]
+    fn standalone_statement_is_a_logical_line_that_doesnt_open_scope() {
+        let module = parse("def foo():\n    return 1\n");
+        let Stmt::FunctionDef(func) = &module.body[0] else {
+            panic!("expected a function def")
+        };
+        let any = AnyNodeRef::from(&func.body[0]);
+        assert_eq!(node_indent_scope(any), Some(ScopeKind::Standalone));
+        assert!(is_logical_line(any));
+        assert!(!opens_indent_scope(any));
+    }
+
+    #[test]
+    fn match_case_is_a_clause_fragment_that_opens_scope() {
+        let module = parse("match x:\n    case 1:\n        pass\n");
+        let Stmt::Match(match_stmt) = &module.body[0] else {
+            panic!("expected a match statement")
+        };
+        let any = AnyNodeRef::from(&match_stmt.cases[0]);
+        assert_eq!(node_indent_scope(any), Some(ScopeKind::ClauseFragment));
+        assert!(opens_indent_scope(any));
+    }
+
+    #[test]
+    fn decorator_is_a_clause_fragment_that_doesnt_open_scope() {
+        let module = parse("@decorator\ndef foo():\n    pass\n");
+        let Stmt::FunctionDef(func) = &module.body[0] else {
+            panic!("expected a function def")
+        };
+        let any = AnyNodeRef::from(&func.decorator_list[0]);
+        assert_eq!(node_indent_scope(any), Some(ScopeKind::ClauseFragment));
+        assert!(!opens_indent_scope(any));
+    }
+
+    #[test]
+    fn ordinary_expressions_are_not_indent_scope_nodes() {
+        let module = parse("x = 1\n");
+        let Stmt::Assign(assign) = &module.body[0] else {
+            panic!("expected an assignment")
+        };
+        let any = AnyNodeRef::from(assign.value.as_ref());
+        assert!(node_indent_scope(any).is_none());
+        assert!(!is_logical_line(any));
+        assert!(!is_formattable_subexpression(any));
+    }
+}
+
+#[cfg(test)]
+mod subexpression_range_tests {
+    use super::format_range;
+    use crate::PyFormatOptions;
+    use ruff_text_size::{TextRange, TextSize};
+
+    fn range_of(source: &str, needle: &str) -> TextRange {
+        let start = source.find(needle).expect("needle not found in source");
+        TextRange::at(
+            TextSize::try_from(start).unwrap(),
+            TextSize::try_from(needle.len()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn narrows_to_a_call_expression() {
+        let source = "result = some_call(1, 2, 3)\n";
+        let range = range_of(source, "some_call(1, 2, 3)");
+
+        let printed = format_range(source, range, PyFormatOptions::default()).unwrap();
+        let covering = &source[printed.range()];
+        assert!(
+            !covering.contains("result ="),
+            "expected the search to narrow into the call expression, got: {covering:?}"
+        );
+    }
+
+    #[test]
+    fn narrows_to_a_dict_literal() {
+        let source = "x = {1: 2, 3: 4}\n";
+        let range = range_of(source, "{1: 2, 3: 4}");
+
+        let printed = format_range(source, range, PyFormatOptions::default()).unwrap();
+        let covering = &source[printed.range()];
+        assert!(
+            !covering.contains("x ="),
+            "expected the search to narrow into the dict literal, got: {covering:?}"
+        );
+    }
+
+    #[test]
+    fn narrows_to_a_call_inside_a_bin_op() {
+        let source = "total = (a + b) * helper(1, 2, 3, 4, 5, 6, 7, 8, 9, 10)\n";
+        let range = range_of(source, "helper(1, 2, 3, 4, 5, 6, 7, 8, 9, 10)");
+
+        let printed = format_range(source, range, PyFormatOptions::default()).unwrap();
+        let covering = &source[printed.range()];
+        assert!(
+            !covering.contains("total ="),
+            "expected the search to narrow into the call expression, got: {covering:?}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod structural_indent_fallback_tests {
+    use super::format_range;
+    use crate::PyFormatOptions;
+    use ruff_text_size::{TextRange, TextSize};
+
+    /// Builds a range covering exactly the first occurrence of `needle` in `source`.
+    fn range_of(source: &str, needle: &str) -> TextRange {
+        let start = source.find(needle).expect("needle not found in source");
+        TextRange::at(
+            TextSize::try_from(start).unwrap(),
+            TextSize::try_from(needle.len()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn mismatched_space_indent_falls_back_to_structural_level() {
+        // `y = 2`'s own indentation (7 spaces) is a multiple of neither the configured nor the
+        // detected width (4), so the string-based `indent_level` can't place it. The structural
+        // fallback (2 scope-opening ancestors: `def`, `if`) should still let the search descend
+        // to `y = 2` itself instead of stopping at the enclosing `if`.
+        let source = "def foo():\n    if True:\n       x = 1\n       y = 2\n";
+        let range = range_of(source, "y = 2");
+
+        let printed = format_range(source, range, PyFormatOptions::default()).unwrap();
+        let covering = &source[printed.range()];
+        assert!(
+            !covering.contains("x = 1"),
+            "expected the search to narrow past the sibling statement, got: {covering:?}"
+        );
+    }
+
+    #[test]
+    fn tab_indented_body_under_space_configured_style_falls_back() {
+        // The `if` body's indentation ends in a tab (`    \t`) while the file's dominant (and
+        // configured) style is spaces, so neither the configured nor the detected `(style, width)`
+        // pair can parse `b = 2`'s leading whitespace. The structural fallback still lets range
+        // formatting narrow to the single statement instead of widening to the whole `if` body.
+        let source = "def foo():\n    if True:\n    \ta = 1\n    \tb = 2\n";
+        let range = range_of(source, "b = 2");
+
+        let printed = format_range(source, range, PyFormatOptions::default()).unwrap();
+        let covering = &source[printed.range()];
+        assert!(
+            !covering.contains("a = 1"),
+            "expected the search to narrow past the sibling statement, got: {covering:?}"
+        );
+    }
+
+    #[test]
+    fn deeply_nested_match_case_falls_back_to_structural_level() {
+        // `b = 2`'s indentation (15 spaces) isn't a multiple of 4, so string-based detection
+        // fails, but the structural ancestor count (`match`, `case`, `match`, `case`) still lets
+        // the search descend past the outer `case 2:` clause to the individual statement.
+        let source = "match x:\n    case 1:\n        match y:\n            case 2:\n               a = 1\n               b = 2\n";
+        let range = range_of(source, "b = 2");
+
+        let printed = format_range(source, range, PyFormatOptions::default()).unwrap();
+        let covering = &source[printed.range()];
+        assert!(
+            !covering.contains("a = 1"),
+            "expected the search to narrow past the sibling statement, got: {covering:?}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod format_ranges_tests {
+    use super::format_ranges;
+    use crate::PyFormatOptions;
+    use ruff_text_size::{TextRange, TextSize};
+
+    fn range_of(source: &str, needle: &str) -> TextRange {
+        let start = source.find(needle).expect("needle not found in source");
+        TextRange::at(
+            TextSize::try_from(start).unwrap(),
+            TextSize::try_from(needle.len()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn adjacent_sibling_statements_are_coalesced_without_overlap() {
+        // Each range only needs its own statement, but the two statements are adjacent (only a
+        // newline between them), so formatting them independently could return two `PrintedRange`s
+        // that overlap at the boundary. They should be coalesced into a single formatted pass.
+        let source = "def foo():\n    a = 1\n    b = 2\n    c = 3\n";
+        let ranges = [range_of(source, "a = 1"), range_of(source, "b = 2")];
+
+        let printed = format_ranges(source, &ranges, PyFormatOptions::default()).unwrap();
+        assert_eq!(printed.len(), 2);
+        assert!(printed[0].range().end() <= printed[1].range().start());
+    }
+
+    #[test]
+    fn nested_ranges_reuse_the_outer_enclosing_node() {
+        // The first range's enclosing node is the entire function, the second's only the inner
+        // statement; the inner enclosing node is contained by the outer one, so both resolve
+        // against the same (outer) formatting pass rather than being formatted twice, and the
+        // two results still don't overlap.
+        let source = "def foo():\n    a = 1\n    b = 2\n";
+        let ranges = [range_of(source, "def foo():"), range_of(source, "b = 2")];
+
+        let printed = format_ranges(source, &ranges, PyFormatOptions::default()).unwrap();
+        assert_eq!(printed.len(), 2);
+        assert!(printed[0].range().end() <= printed[1].range().start());
+    }
+}